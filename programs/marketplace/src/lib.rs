@@ -1,6 +1,18 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Burn, CloseAccount, MintTo, SetAuthority, Token, TokenAccount, Transfer, Mint};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token_2022::{self, spl_token_2022, Token2022};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use flii_token::state::StakePosition;
+use mpl_token_metadata::accounts::Metadata;
+use mpl_token_metadata::instructions::{
+    CreateMetadataAccountV3Cpi, CreateMetadataAccountV3CpiAccounts,
+    CreateMetadataAccountV3InstructionArgs,
+};
+use mpl_token_metadata::types::DataV2;
+use spl_token::instruction::AuthorityType;
 
 declare_id!("3RAeCuRqF9kGXyXwk9Lynj19fuxJJj5RXCga9KiihaKF");
 
@@ -56,6 +68,13 @@ pub mod marketplace {
         component.total_rewards_earned = 0;  // Track FLII rewards earned
         component.created_at = Clock::get()?.unix_timestamp;
 
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        if creator_profile.creator == Pubkey::default() {
+            creator_profile.initialize(component.creator, component.created_at);
+        }
+        require!(!creator_profile.is_banned, ErrorCode::CreatorBanned);
+        creator_profile.total_listings += 1;
+
         let marketplace = &mut ctx.accounts.marketplace;
         marketplace.total_listings += 1;
 
@@ -64,16 +83,108 @@ pub mod marketplace {
             creator: component.creator,
             price: component.price,
             token_mint: marketplace.flii_token_mint,
+            creator_verified: creator_profile.verified,
+        });
+
+        Ok(())
+    }
+
+    /// Moves a component listed under the old global `[b"component", component_id]`
+    /// seeds to the creator-namespaced address, keeping its sales history and
+    /// moderation state, and returns the old account's rent to the creator.
+    pub fn migrate_component(
+        ctx: Context<MigrateComponent>,
+        component_id: String,
+    ) -> Result<()> {
+        let legacy_info = ctx.accounts.legacy_component.to_account_info();
+
+        let (legacy, is_frozen, freeze_reason) = {
+            let data = legacy_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Component::DISCRIMINATOR,
+                ErrorCode::InvalidComponentAccount
+            );
+            let mut rest: &[u8] = &data[8..];
+            let legacy = LegacyComponent::deserialize(&mut rest)?;
+            // Moderation fields were appended after the legacy layout. Accounts
+            // written before then hold zero padding there, which reads as not
+            // frozen; the fallback covers accounts sized exactly to the layout.
+            let (is_frozen, freeze_reason) =
+                <(bool, u16)>::deserialize(&mut rest).unwrap_or((false, 0));
+            (legacy, is_frozen, freeze_reason)
+        };
+        require!(
+            legacy.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+        require!(legacy.component_id == component_id, ErrorCode::InvalidComponentAccount);
+
+        let component = &mut ctx.accounts.component;
+        component.creator = legacy.creator;
+        component.component_id = legacy.component_id;
+        component.price = legacy.price;
+        component.metadata_uri = legacy.metadata_uri;
+        component.is_active = legacy.is_active;
+        component.total_sales = legacy.total_sales;
+        component.total_rewards_earned = legacy.total_rewards_earned;
+        component.created_at = legacy.created_at;
+        component.is_frozen = is_frozen;
+        component.freeze_reason = freeze_reason;
+
+        // Legacy listings predate creator profiles, so count them now
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        if creator_profile.creator == Pubkey::default() {
+            creator_profile.initialize(component.creator, Clock::get()?.unix_timestamp);
+        }
+        require!(!creator_profile.is_banned, ErrorCode::CreatorBanned);
+        creator_profile.total_listings += 1;
+        creator_profile.total_sales += component.total_sales;
+
+        // Close the legacy account
+        let creator_info = ctx.accounts.creator.to_account_info();
+        **creator_info.try_borrow_mut_lamports()? += legacy_info.lamports();
+        **legacy_info.try_borrow_mut_lamports()? = 0;
+        legacy_info.assign(&system_program::ID);
+        legacy_info.realloc(0, false)?;
+
+        emit!(ComponentMigrated {
+            component_id: component.component_id.clone(),
+            creator: component.creator,
+            legacy_address: legacy_info.key(),
+            address: component.key(),
         });
 
         Ok(())
     }
 
+    /// Buys a license for `recipient`, defaulting to the buyer. The buyer
+    /// pays; the license receipt is issued to the recipient. Fails if the
+    /// amount debited, the current price plus the 2% staking reward, is
+    /// above `max_price`.
     pub fn purchase_component(
         ctx: Context<PurchaseComponent>,
+        recipient: Option<Pubkey>,
+        gift_message_hash: Option<[u8; 32]>,
+        max_price: u64,
     ) -> Result<()> {
         let component = &mut ctx.accounts.component;
+        require!(!component.is_frozen, ErrorCode::ComponentFrozen);
         require!(component.is_active, ErrorCode::ComponentNotActive);
+        require!(!component.is_sold_out(), ErrorCode::SoldOut);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= component.sale_start, ErrorCode::SaleNotStarted);
+        if let Some(auction) = &component.dutch_auction {
+            require!(now >= auction.start_time, ErrorCode::SaleNotStarted);
+        }
+
+        let wallet_purchases = &mut ctx.accounts.wallet_purchases;
+        if let Some(max_per_wallet) = component.max_per_wallet {
+            require!(
+                wallet_purchases.count < max_per_wallet,
+                ErrorCode::WalletLimitReached
+            );
+        }
         
         // Verify FLII token mint
         require_keys_eq!(
@@ -83,22 +194,36 @@ pub mod marketplace {
         );
 
         let marketplace = &mut ctx.accounts.marketplace;
-        let total_price = component.price;  // Price in FLII tokens
+        let total_price = component.effective_price(now);  // Price in FLII tokens
+        // Calculate staking rewards (e.g., 2% bonus for using FLII)
+        let staking_reward = Component::staking_reward(total_price);
+        require!(
+            total_price.checked_add(staking_reward).unwrap() <= max_price,
+            ErrorCode::PriceAboveMax
+        );
         
-        // Calculate fees and rewards
+        // Calculate fees and rewards, discounted by the buyer's FLII holdings.
+        // Staked FLII counts with its lock boost.
+        let mut holdings = ctx.accounts.buyer_flii_token_account.amount;
+        if let Some(position) = &ctx.accounts.buyer_stake_position {
+            let (stake_pool, _) = Pubkey::find_program_address(
+                &[b"stake_pool", marketplace.flii_token_mint.as_ref()],
+                &flii_token::ID,
+            );
+            require_keys_eq!(position.pool, stake_pool, ErrorCode::InvalidStakePosition);
+            holdings = holdings.checked_add(position.boosted_amount(now)).unwrap();
+        }
+        let fee_tier = marketplace.fee_tier(holdings);
+        let fee_percentage = match fee_tier {
+            Some(index) => marketplace.fee_tiers[index as usize].fee_percentage,
+            None => marketplace.fee_percentage,
+        };
         let platform_fee = total_price
-            .checked_mul(marketplace.fee_percentage as u64)
+            .checked_mul(fee_percentage as u64)
             .unwrap()
             .checked_div(10000)
             .unwrap();
         
-        // Calculate staking rewards (e.g., 2% bonus for using FLII)
-        let staking_reward = total_price
-            .checked_mul(200)  // 2% reward
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
-            
         let creator_amount = total_price
             .checked_sub(platform_fee)
             .unwrap()
@@ -115,192 +240,4056 @@ pub mod marketplace {
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         token::transfer(cpi_ctx, creator_amount)?;
 
-        // Transfer platform fee in FLII to marketplace treasury
-        if platform_fee > 0 {
+        // Burn the configured share of the platform fee
+        let burned = platform_fee
+            .checked_mul(marketplace.burn_bps as u64)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap();
+        if burned > 0 {
+            let cpi_accounts_burn = Burn {
+                mint: ctx.accounts.flii_token_mint.to_account_info(),
+                from: ctx.accounts.buyer_flii_token_account.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            };
+            let cpi_program_burn = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx_burn = CpiContext::new(cpi_program_burn, cpi_accounts_burn);
+            token::burn(cpi_ctx_burn, burned)?;
+
+            marketplace.total_burned += burned;
+            emit!(FeeBurned {
+                amount: burned,
+                total_burned: marketplace.total_burned,
+            });
+        }
+
+        // Collect the rest of the platform fee in FLII in the fee vault
+        let treasury_fee = platform_fee.checked_sub(burned).unwrap();
+        if treasury_fee > 0 {
             let cpi_accounts_fee = Transfer {
                 from: ctx.accounts.buyer_flii_token_account.to_account_info(),
-                to: ctx.accounts.treasury_flii_token_account.to_account_info(),
+                to: ctx.accounts.fee_vault.to_account_info(),
                 authority: ctx.accounts.buyer.to_account_info(),
             };
             let cpi_program_fee = ctx.accounts.token_program.to_account_info();
             let cpi_ctx_fee = CpiContext::new(cpi_program_fee, cpi_accounts_fee);
-            token::transfer(cpi_ctx_fee, platform_fee)?;
+            token::transfer(cpi_ctx_fee, treasury_fee)?;
+            marketplace.total_fees_collected += treasury_fee;
         }
 
         // Update stats
-        component.total_sales += 1;
-        component.total_rewards_earned += staking_reward;
-        marketplace.total_volume += total_price;
-        marketplace.total_sales += 1;
+        let edition = component.record_sale(
+            marketplace,
+            &mut ctx.accounts.creator_profile,
+            total_price,
+            creator_amount,
+        );
+
+        // Issue the license receipt
+        let buyer = ctx.accounts.buyer.key();
+        let recipient = recipient.unwrap_or(buyer);
+        let component_key = component.key();
+        wallet_purchases.component = component_key;
+        wallet_purchases.wallet = recipient;
+        wallet_purchases.count += 1;
+
+        let component_id = component.component_id.clone();
+        let list_price = component.price;
+        let token_mint = marketplace.flii_token_mint;
+        let license_mint = match component.license_mode {
+            LicenseMode::Receipt => {
+                require!(
+                    ctx.accounts.license_mint.is_none() && ctx.accounts.soulbound_mint.is_none(),
+                    ErrorCode::UnexpectedLicenseTokenAccounts
+                );
+                None
+            }
+            LicenseMode::Transferable => {
+                let metadata_uri = component.metadata_uri.clone();
+                Some(ctx.accounts.mint_license_token(
+                    &metadata_uri,
+                    edition,
+                    ctx.bumps.license_mint,
+                )?)
+            }
+            LicenseMode::Soulbound => {
+                Some(ctx.accounts.mint_soulbound_token(ctx.bumps.soulbound_mint)?)
+            }
+        };
+
+        let license = &mut ctx.accounts.license;
+        license.record(component_key, recipient, buyer, total_price, now, edition);
+        license.gift_message_hash = gift_message_hash;
+        license.license_mint = license_mint;
 
         emit!(ComponentPurchased {
-            component_id: component.component_id.clone(),
-            buyer: ctx.accounts.buyer.key(),
-            price: component.price,
+            component_id,
+            buyer,
+            price: list_price,
+            effective_price: total_price,
             rewards_earned: staking_reward,
-            token_mint: marketplace.flii_token_mint,
+            token_mint,
+            recipient,
+            license: license.key(),
+            gift_message_hash,
+            edition,
+            platform_fee,
+            fee_tier,
+            burned,
         });
 
         Ok(())
     }
 
-    pub fn delist_component(
-        ctx: Context<DelistComponent>,
+    /// Starts an English auction for one license of a component. Bids are
+    /// escrowed in a token account owned by the auction PDA.
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        start_time: i64,
+        end_time: i64,
+        reserve_price: u64,
+        min_increment: u64,
+        extension_window: i64,
     ) -> Result<()> {
-        let component = &mut ctx.accounts.component;
+        let component = &ctx.accounts.component;
         require!(
             component.creator == ctx.accounts.creator.key(),
             ErrorCode::UnauthorizedCreator
         );
-        
-        component.is_active = false;
-        
+        require!(!component.is_frozen, ErrorCode::ComponentFrozen);
+        require!(component.is_active, ErrorCode::ComponentNotActive);
+        require!(!component.is_sold_out(), ErrorCode::SoldOut);
+        require!(
+            component.license_mode == LicenseMode::Receipt,
+            ErrorCode::LicenseModeNotSupported
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            start_time < end_time && end_time > now && extension_window >= 0,
+            ErrorCode::InvalidAuctionSchedule
+        );
+        require!(min_increment > 0, ErrorCode::InvalidPrice);
+
+        let auction = &mut ctx.accounts.auction;
+        auction.component = component.key();
+        auction.creator = component.creator;
+        auction.reserve_price = reserve_price;
+        auction.min_increment = min_increment;
+        auction.start_time = start_time;
+        auction.end_time = end_time;
+        auction.extension_window = extension_window;
+        auction.highest_bid = 0;
+        auction.highest_bidder = None;
+        auction.bump = ctx.bumps.auction;
+
+        emit!(AuctionCreated {
+            component_id: component.component_id.clone(),
+            auction: auction.key(),
+            reserve_price,
+            start_time,
+            end_time,
+        });
+
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + Marketplace::SIZE,
-        seeds = [b"marketplace"],
-        bump
-    )]
-    pub marketplace: Account<'info, Marketplace>,
-    #[account(
-        constraint = flii_token_mint.key() == FLII_TOKEN_MINT.parse::<Pubkey>().unwrap()
-    )]
-    pub flii_token_mint: Account<'info, Mint>,
-    /// CHECK: Treasury wallet for collecting fees
-    pub treasury_wallet: AccountInfo<'info>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-}
+    /// Escrows a bid and refunds the bid it replaces. Bids in the last
+    /// `extension_window` seconds push the end time back so late bidders
+    /// cannot snipe the auction.
+    pub fn place_bid(ctx: Context<PlaceBid>, bid_amount: u64) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= auction.start_time, ErrorCode::AuctionNotStarted);
+        require!(now < auction.end_time, ErrorCode::AuctionEnded);
 
-#[derive(Accounts)]
-#[instruction(component_id: String)]
-pub struct ListComponent<'info> {
-    #[account(
-        init,
-        payer = creator,
-        space = 8 + Component::SIZE,
-        seeds = [b"component", component_id.as_bytes()],
-        bump
-    )]
-    pub component: Account<'info, Component>,
-    #[account(mut)]
-    pub marketplace: Account<'info, Marketplace>,
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        let min_bid = if auction.highest_bidder.is_some() {
+            auction.highest_bid.checked_add(auction.min_increment).unwrap()
+        } else {
+            auction.min_increment
+        };
+        require!(bid_amount >= min_bid, ErrorCode::BidTooLow);
 
-#[derive(Accounts)]
-pub struct PurchaseComponent<'info> {
-    #[account(mut)]
-    pub component: Account<'info, Component>,
-    #[account(mut)]
-    pub marketplace: Account<'info, Marketplace>,
-    #[account(
-        constraint = flii_token_mint.key() == FLII_TOKEN_MINT.parse::<Pubkey>().unwrap()
-    )]
-    pub flii_token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    #[account(
-        mut,
-        constraint = buyer_flii_token_account.mint == flii_token_mint.key(),
-        constraint = buyer_flii_token_account.owner == buyer.key()
-    )]
-    pub buyer_flii_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        constraint = creator_flii_token_account.mint == flii_token_mint.key()
-    )]
-    pub creator_flii_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        constraint = treasury_flii_token_account.mint == flii_token_mint.key(),
-        constraint = treasury_flii_token_account.owner == marketplace.treasury_wallet
-    )]
-    pub treasury_flii_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-}
+        // Refund previous highest bidder if exists
+        if auction.highest_bidder.is_some() {
+            let refund_account = ctx
+                .accounts
+                .previous_bidder_flii_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingRefundAccount)?;
+            let component_key = auction.component;
+            let seeds = &[b"auction".as_ref(), component_key.as_ref(), &[auction.bump]];
+            let signer = &[&seeds[..]];
+            let refund_accounts = Transfer {
+                from: ctx.accounts.escrow_flii_token_account.to_account_info(),
+                to: refund_account.to_account_info(),
+                authority: auction.to_account_info(),
+            };
+            let refund_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                refund_accounts,
+                signer,
+            );
+            token::transfer(refund_ctx, auction.highest_bid)?;
+        }
 
-#[derive(Accounts)]
-pub struct DelistComponent<'info> {
-    #[account(mut)]
-    pub component: Account<'info, Component>,
-    pub creator: Signer<'info>,
-}
+        // Transfer new bid to escrow
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.bidder_flii_token_account.to_account_info(),
+            to: ctx.accounts.escrow_flii_token_account.to_account_info(),
+            authority: ctx.accounts.bidder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, bid_amount)?;
 
-#[account]
-pub struct Marketplace {
-    pub authority: Pubkey,
-    pub fee_percentage: u16,
-    pub total_volume: u64,
-    pub total_listings: u64,
-    pub total_sales: u64,
-    pub flii_token_mint: Pubkey,
-    pub treasury_wallet: Pubkey,
-}
+        auction.highest_bid = bid_amount;
+        auction.highest_bidder = Some(ctx.accounts.bidder.key());
+        if auction.end_time - now < auction.extension_window {
+            auction.end_time = now + auction.extension_window;
+        }
 
-impl Marketplace {
-    pub const SIZE: usize = 32 + 2 + 8 + 8 + 8 + 32 + 32;
-}
+        emit!(BidPlaced {
+            auction: auction.key(),
+            bidder: ctx.accounts.bidder.key(),
+            bid_amount,
+            end_time: auction.end_time,
+        });
 
-#[account]
-pub struct Component {
-    pub creator: Pubkey,
-    pub component_id: String,
-    pub price: u64,  // Price in FLII tokens
-    pub metadata_uri: String,
-    pub is_active: bool,
-    pub total_sales: u64,
-    pub total_rewards_earned: u64,  // Total FLII rewards earned
-    pub created_at: i64,
-}
+        Ok(())
+    }
 
-impl Component {
-    pub const SIZE: usize = 32 + 36 + 8 + 200 + 1 + 8 + 8 + 8;
-}
+    /// Closes an auction after its end time. Anyone can call it. If the
+    /// reserve was met, the component can still be sold and the winner is
+    /// within the per-wallet limit, the escrow pays the platform fee and the
+    /// creator and the winner gets a license; otherwise the highest bid is
+    /// refunded. FLII sent to the escrow outside of bids goes to the creator.
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= auction.end_time, ErrorCode::AuctionNotEnded);
 
-#[event]
-pub struct ComponentListed {
-    pub component_id: String,
-    pub creator: Pubkey,
-    pub price: u64,  // Price in FLII tokens
-    pub token_mint: Pubkey,
-}
+        let component = &mut ctx.accounts.component;
+        require!(
+            auction.highest_bidder.is_none() || ctx.accounts.wallet_purchases.is_some(),
+            ErrorCode::MissingWalletPurchasesAccount
+        );
+        let wallet_count = ctx.accounts.wallet_purchases.as_ref().map_or(0, |w| w.count);
+        let winner = auction
+            .highest_bidder
+            .filter(|_| auction.highest_bid >= auction.reserve_price)
+            .filter(|_| !component.is_frozen && component.is_active && !component.is_sold_out())
+            .filter(|_| component.license_mode == LicenseMode::Receipt)
+            .filter(|_| !matches!(component.max_per_wallet, Some(max) if wallet_count >= max));
+        // A license created for an unsold auction would occupy the address
+        // of the component's next sale.
+        require!(
+            winner.is_some() == ctx.accounts.license.is_some(),
+            ErrorCode::MissingLicenseAccount
+        );
 
-#[event]
-pub struct ComponentPurchased {
-    pub component_id: String,
-    pub buyer: Pubkey,
-    pub price: u64,  // Price in FLII tokens
-    pub rewards_earned: u64,  // FLII rewards earned
-    pub token_mint: Pubkey,
-}
+        let component_key = auction.component;
+        let seeds = &[b"auction".as_ref(), component_key.as_ref(), &[auction.bump]];
+        let signer = &[&seeds[..]];
+        let winning_bid = auction.highest_bid;
+        let mut license_key = None;
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid fee percentage")]
-    InvalidFeePercentage,
-    #[msg("Invalid price")]
-    InvalidPrice,
-    #[msg("Component ID too long")]
-    ComponentIdTooLong,
-    #[msg("Component not active")]
-    ComponentNotActive,
-    #[msg("Unauthorized creator")]
-    UnauthorizedCreator,
-    #[msg("Invalid token mint - must use FLII token")]
-    InvalidTokenMint,
-    #[msg("Insufficient FLII token balance")]
-    InsufficientTokenBalance,
+        // Anyone can send FLII to the escrow; whatever is not the highest bid
+        // goes to the creator so the escrow can be closed.
+        let escrowed_bid = if auction.highest_bidder.is_some() { winning_bid } else { 0 };
+        let excess = ctx
+            .accounts
+            .escrow_flii_token_account
+            .amount
+            .checked_sub(escrowed_bid)
+            .unwrap();
+        if excess > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_flii_token_account.to_account_info(),
+                to: ctx.accounts.creator_flii_token_account.to_account_info(),
+                authority: auction.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            );
+            token::transfer(cpi_ctx, excess)?;
+        }
+
+        if let Some(winner) = winner {
+            let marketplace = &mut ctx.accounts.marketplace;
+            let platform_fee = marketplace.platform_fee(winning_bid);
+            let creator_amount = winning_bid.checked_sub(platform_fee).unwrap();
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_flii_token_account.to_account_info(),
+                to: ctx.accounts.creator_flii_token_account.to_account_info(),
+                authority: auction.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            );
+            token::transfer(cpi_ctx, creator_amount)?;
+
+            if platform_fee > 0 {
+                let cpi_accounts_fee = Transfer {
+                    from: ctx.accounts.escrow_flii_token_account.to_account_info(),
+                    to: ctx.accounts.fee_vault.to_account_info(),
+                    authority: auction.to_account_info(),
+                };
+                let cpi_ctx_fee = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts_fee,
+                    signer,
+                );
+                token::transfer(cpi_ctx_fee, platform_fee)?;
+                marketplace.total_fees_collected += platform_fee;
+            }
+
+            // FLII staking rewards, as in `purchase_component`, are part of
+            // the bid
+            let edition = component.record_sale(
+                marketplace,
+                &mut ctx.accounts.creator_profile,
+                winning_bid,
+                creator_amount,
+            );
+
+            let wallet_purchases = ctx
+                .accounts
+                .wallet_purchases
+                .as_mut()
+                .ok_or(ErrorCode::MissingWalletPurchasesAccount)?;
+            wallet_purchases.component = component.key();
+            wallet_purchases.wallet = winner;
+            wallet_purchases.count += 1;
+
+            let license = ctx
+                .accounts
+                .license
+                .as_mut()
+                .ok_or(ErrorCode::MissingLicenseAccount)?;
+            license.record(component.key(), winner, winner, winning_bid, now, edition);
+            license_key = Some(license.key());
+        } else if auction.highest_bidder.is_some() {
+            let refund_account = ctx
+                .accounts
+                .bidder_flii_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingRefundAccount)?;
+            let refund_accounts = Transfer {
+                from: ctx.accounts.escrow_flii_token_account.to_account_info(),
+                to: refund_account.to_account_info(),
+                authority: auction.to_account_info(),
+            };
+            let refund_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                refund_accounts,
+                signer,
+            );
+            token::transfer(refund_ctx, winning_bid)?;
+        }
+
+        let close_accounts = CloseAccount {
+            account: ctx.accounts.escrow_flii_token_account.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: auction.to_account_info(),
+        };
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            close_accounts,
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
+        emit!(AuctionEnded {
+            component_id: component.component_id.clone(),
+            auction: auction.key(),
+            winner,
+            winning_bid: if winner.is_some() { winning_bid } else { 0 },
+            license: license_key,
+        });
+
+        Ok(())
+    }
+
+    /// Cancels an auction that has not received any bids.
+    pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        require!(auction.highest_bidder.is_none(), ErrorCode::AuctionHasBids);
+
+        let component_key = auction.component;
+        let seeds = &[b"auction".as_ref(), component_key.as_ref(), &[auction.bump]];
+        let signer = &[&seeds[..]];
+
+        // Without bids, anything in the escrow was sent to it directly
+        let stray = ctx.accounts.escrow_flii_token_account.amount;
+        if stray > 0 {
+            let creator_account = ctx
+                .accounts
+                .creator_flii_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingRefundAccount)?;
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_flii_token_account.to_account_info(),
+                to: creator_account.to_account_info(),
+                authority: auction.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            );
+            token::transfer(cpi_ctx, stray)?;
+        }
+
+        let close_accounts = CloseAccount {
+            account: ctx.accounts.escrow_flii_token_account.to_account_info(),
+            destination: ctx.accounts.creator.to_account_info(),
+            authority: auction.to_account_info(),
+        };
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            close_accounts,
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
+        emit!(AuctionCancelled {
+            auction: auction.key(),
+            creator: auction.creator,
+        });
+
+        Ok(())
+    }
+
+    /// Escrows `amount` FLII as an offer for one license of a component.
+    /// A buyer has at most one open offer per component.
+    pub fn make_offer(
+        ctx: Context<MakeOffer>,
+        amount: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidPrice);
+        let component = &ctx.accounts.component;
+        require!(!component.is_frozen, ErrorCode::ComponentFrozen);
+        require!(component.is_active, ErrorCode::ComponentNotActive);
+        require!(
+            component.license_mode == LicenseMode::Receipt,
+            ErrorCode::LicenseModeNotSupported
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > now, ErrorCode::OfferExpired);
+        }
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.buyer_flii_token_account.to_account_info(),
+            to: ctx.accounts.offer_vault.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        let offer = &mut ctx.accounts.offer;
+        offer.buyer = ctx.accounts.buyer.key();
+        offer.component = component.key();
+        offer.amount = amount;
+        offer.created_at = now;
+        offer.expires_at = expires_at;
+        offer.bump = ctx.bumps.offer;
+
+        emit!(OfferMade {
+            offer: offer.key(),
+            component_id: component.component_id.clone(),
+            buyer: offer.buyer,
+            amount,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_offer(ctx: Context<CloseOffer>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.offer.buyer,
+            ErrorCode::UnauthorizedBuyer
+        );
+
+        refund_offer(&ctx)?;
+
+        emit!(OfferCancelled {
+            offer: ctx.accounts.offer.key(),
+            buyer: ctx.accounts.offer.buyer,
+            expired: false,
+        });
+
+        Ok(())
+    }
+
+    /// Refunds an expired offer to its buyer. Anyone can call it.
+    pub fn reclaim_expired_offer(ctx: Context<CloseOffer>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(ctx.accounts.offer.is_expired(now), ErrorCode::OfferNotExpired);
+
+        refund_offer(&ctx)?;
+
+        emit!(OfferCancelled {
+            offer: ctx.accounts.offer.key(),
+            buyer: ctx.accounts.offer.buyer,
+            expired: true,
+        });
+
+        Ok(())
+    }
+
+    /// Sells one license to the offer's buyer at the offered amount, paying
+    /// the platform fee and the creator out of the offer vault.
+    pub fn accept_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+        require!(!component.is_frozen, ErrorCode::ComponentFrozen);
+        require!(component.is_active, ErrorCode::ComponentNotActive);
+        require!(!component.is_sold_out(), ErrorCode::SoldOut);
+        require!(
+            component.license_mode == LicenseMode::Receipt,
+            ErrorCode::LicenseModeNotSupported
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let offer = &ctx.accounts.offer;
+        require!(!offer.is_expired(now), ErrorCode::OfferExpired);
+
+        let wallet_purchases = &mut ctx.accounts.wallet_purchases;
+        if let Some(max_per_wallet) = component.max_per_wallet {
+            require!(
+                wallet_purchases.count < max_per_wallet,
+                ErrorCode::WalletLimitReached
+            );
+        }
+
+        let marketplace = &mut ctx.accounts.marketplace;
+        let amount = offer.amount;
+        let platform_fee = marketplace.platform_fee(amount);
+        let creator_amount = amount.checked_sub(platform_fee).unwrap();
+
+        let component_key = offer.component;
+        let buyer_key = offer.buyer;
+        let seeds = &[
+            b"offer".as_ref(),
+            component_key.as_ref(),
+            buyer_key.as_ref(),
+            &[offer.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.offer_vault.to_account_info(),
+            to: ctx.accounts.creator_flii_token_account.to_account_info(),
+            authority: offer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token::transfer(cpi_ctx, creator_amount)?;
+
+        if platform_fee > 0 {
+            let cpi_accounts_fee = Transfer {
+                from: ctx.accounts.offer_vault.to_account_info(),
+                to: ctx.accounts.fee_vault.to_account_info(),
+                authority: offer.to_account_info(),
+            };
+            let cpi_ctx_fee = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts_fee,
+                signer,
+            );
+            token::transfer(cpi_ctx_fee, platform_fee)?;
+            marketplace.total_fees_collected += platform_fee;
+        }
+
+        // Return anything else in the vault to the buyer so it can be closed
+        let excess = ctx.accounts.offer_vault.amount.checked_sub(amount).unwrap();
+        if excess > 0 {
+            let refund_accounts = Transfer {
+                from: ctx.accounts.offer_vault.to_account_info(),
+                to: ctx.accounts.buyer_flii_token_account.to_account_info(),
+                authority: offer.to_account_info(),
+            };
+            let refund_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                refund_accounts,
+                signer,
+            );
+            token::transfer(refund_ctx, excess)?;
+        }
+
+        let close_accounts = CloseAccount {
+            account: ctx.accounts.offer_vault.to_account_info(),
+            destination: ctx.accounts.buyer.to_account_info(),
+            authority: offer.to_account_info(),
+        };
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            close_accounts,
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
+        // Update stats; staking rewards are part of the offer, as with
+        // auction bids
+        let edition = component.record_sale(
+            marketplace,
+            &mut ctx.accounts.creator_profile,
+            amount,
+            creator_amount,
+        );
+
+        wallet_purchases.component = component.key();
+        wallet_purchases.wallet = buyer_key;
+        wallet_purchases.count += 1;
+
+        let license = &mut ctx.accounts.license;
+        license.record(component.key(), buyer_key, buyer_key, amount, now, edition);
+
+        emit!(OfferAccepted {
+            offer: offer.key(),
+            component_id: component.component_id.clone(),
+            buyer: buyer_key,
+            amount,
+            license: license.key(),
+            edition,
+        });
+
+        Ok(())
+    }
+
+    /// Lists an SPL NFT (0 decimals, supply of 1) for a fixed FLII price.
+    /// The NFT is held in a token account owned by the listing PDA.
+    pub fn create_nft_listing(ctx: Context<CreateNFTListing>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+
+        // Transfer NFT to escrow
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.seller_nft_account.to_account_info(),
+            to: ctx.accounts.escrow_nft_account.to_account_info(),
+            authority: ctx.accounts.seller.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, 1)?;
+
+        let listing = &mut ctx.accounts.listing;
+        listing.seller = ctx.accounts.seller.key();
+        listing.nft_mint = ctx.accounts.nft_mint.key();
+        listing.price = price;
+        listing.created_at = Clock::get()?.unix_timestamp;
+        listing.bump = ctx.bumps.listing;
+
+        emit!(NFTListed {
+            listing: listing.key(),
+            seller: listing.seller,
+            nft_mint: listing.nft_mint,
+            price,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_nft_listing(ctx: Context<CancelNFTListing>) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        let seeds = &[b"nft_listing".as_ref(), listing.nft_mint.as_ref(), &[listing.bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow_nft_account.to_account_info(),
+            to: ctx.accounts.seller_nft_account.to_account_info(),
+            authority: listing.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token::transfer(cpi_ctx, 1)?;
+
+        let close_accounts = CloseAccount {
+            account: ctx.accounts.escrow_nft_account.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: listing.to_account_info(),
+        };
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            close_accounts,
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
+        emit!(NFTListingCancelled {
+            listing: listing.key(),
+            seller: listing.seller,
+        });
+
+        Ok(())
+    }
+
+    /// Buys a listed NFT. If the mint has Metaplex metadata, its
+    /// `seller_fee_basis_points` are paid out of the price to each verified
+    /// creator by share; pass one FLII token account per verified creator,
+    /// in metadata order, as remaining accounts. Shares of unverified
+    /// creators stay with the seller.
+    pub fn purchase_nft<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseNFT<'info>>,
+    ) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        let marketplace = &mut ctx.accounts.marketplace;
+        let total_price = listing.price;
+        let platform_fee = marketplace.platform_fee(total_price);
+
+        // Pay creator royalties from the NFT's metadata
+        let mut royalties_paid: u64 = 0;
+        let metadata_info = ctx.accounts.nft_metadata.to_account_info();
+        if !metadata_info.data_is_empty() {
+            let metadata = Metadata::safe_deserialize(&metadata_info.try_borrow_data()?)
+                .map_err(|_| ErrorCode::InvalidNFTMetadata)?;
+            require_keys_eq!(metadata.mint, listing.nft_mint, ErrorCode::InvalidNFTMetadata);
+
+            let royalty = total_price
+                .checked_mul(metadata.seller_fee_basis_points as u64)
+                .unwrap()
+                .checked_div(10000)
+                .unwrap();
+            let verified_creators = metadata
+                .creators
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|c| c.verified && c.share > 0);
+            let mut creator_accounts = ctx.remaining_accounts.iter();
+
+            for creator in verified_creators {
+                let account_info = creator_accounts
+                    .next()
+                    .ok_or(ErrorCode::MissingRoyaltyAccount)?;
+                let creator_account = Account::<TokenAccount>::try_from(account_info)?;
+                require_keys_eq!(
+                    creator_account.owner,
+                    creator.address,
+                    ErrorCode::InvalidRoyaltyAccount
+                );
+                require_keys_eq!(
+                    creator_account.mint,
+                    marketplace.flii_token_mint,
+                    ErrorCode::InvalidRoyaltyAccount
+                );
+
+                let creator_royalty = royalty
+                    .checked_mul(creator.share as u64)
+                    .unwrap()
+                    .checked_div(100)
+                    .unwrap();
+                if creator_royalty == 0 {
+                    continue;
+                }
+
+                let cpi_accounts_royalty = Transfer {
+                    from: ctx.accounts.buyer_flii_token_account.to_account_info(),
+                    to: account_info.clone(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                };
+                let cpi_ctx_royalty = CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts_royalty,
+                );
+                token::transfer(cpi_ctx_royalty, creator_royalty)?;
+                royalties_paid += creator_royalty;
+
+                emit!(RoyaltyPaid {
+                    nft_mint: listing.nft_mint,
+                    creator: creator.address,
+                    amount: creator_royalty,
+                });
+            }
+        }
+
+        let seller_amount = total_price
+            .checked_sub(platform_fee)
+            .unwrap()
+            .checked_sub(royalties_paid)
+            .ok_or(ErrorCode::InvalidNFTMetadata)?;
+
+        // Transfer seller amount in FLII
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.buyer_flii_token_account.to_account_info(),
+            to: ctx.accounts.seller_flii_token_account.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, seller_amount)?;
+
+        // Collect the platform fee in FLII in the fee vault
+        if platform_fee > 0 {
+            let cpi_accounts_fee = Transfer {
+                from: ctx.accounts.buyer_flii_token_account.to_account_info(),
+                to: ctx.accounts.fee_vault.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            };
+            let cpi_ctx_fee =
+                CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts_fee);
+            token::transfer(cpi_ctx_fee, platform_fee)?;
+            marketplace.total_fees_collected += platform_fee;
+        }
+
+        // Release the NFT from escrow
+        let seeds = &[b"nft_listing".as_ref(), listing.nft_mint.as_ref(), &[listing.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts_nft = Transfer {
+            from: ctx.accounts.escrow_nft_account.to_account_info(),
+            to: ctx.accounts.buyer_nft_account.to_account_info(),
+            authority: listing.to_account_info(),
+        };
+        let cpi_ctx_nft = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts_nft,
+            signer,
+        );
+        token::transfer(cpi_ctx_nft, 1)?;
+
+        let close_accounts = CloseAccount {
+            account: ctx.accounts.escrow_nft_account.to_account_info(),
+            destination: ctx.accounts.seller.to_account_info(),
+            authority: listing.to_account_info(),
+        };
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            close_accounts,
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
+        marketplace.total_nft_sales += 1;
+        marketplace.total_resale_volume += total_price;
+
+        emit!(NFTSold {
+            listing: listing.key(),
+            seller: listing.seller,
+            buyer: ctx.accounts.buyer.key(),
+            nft_mint: listing.nft_mint,
+            price: total_price,
+            royalties_paid,
+        });
+
+        Ok(())
+    }
+
+    /// Lists a license for resale at a fixed FLII price. Tokenized licenses
+    /// move their token into an escrow owned by the listing; soulbound
+    /// licenses cannot be resold.
+    pub fn list_license(ctx: Context<ListLicense>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+        let license = &ctx.accounts.license;
+
+        match license.license_mint {
+            None => {
+                require_keys_eq!(
+                    license.owner,
+                    ctx.accounts.seller.key(),
+                    ErrorCode::UnauthorizedSeller
+                );
+            }
+            Some(license_mint) => {
+                let (Some(mint), Some(seller_token_account), Some(escrow)) = (
+                    ctx.accounts.license_mint.as_ref(),
+                    ctx.accounts.seller_license_token_account.as_ref(),
+                    ctx.accounts.escrow_license_token_account.as_ref(),
+                ) else {
+                    return err!(ErrorCode::MissingLicenseTokenAccounts);
+                };
+                require_keys_eq!(mint.key(), license_mint, ErrorCode::InvalidLicenseToken);
+                require_keys_eq!(
+                    seller_token_account.mint,
+                    license_mint,
+                    ErrorCode::InvalidLicenseToken
+                );
+
+                let cpi_accounts = Transfer {
+                    from: seller_token_account.to_account_info(),
+                    to: escrow.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                };
+                let cpi_ctx =
+                    CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, 1)?;
+            }
+        }
+
+        let listing = &mut ctx.accounts.listing;
+        listing.seller = ctx.accounts.seller.key();
+        listing.license = license.key();
+        listing.price = price;
+        listing.created_at = Clock::get()?.unix_timestamp;
+        listing.bump = ctx.bumps.listing;
+
+        emit!(LicenseListed {
+            listing: listing.key(),
+            license: listing.license,
+            seller: listing.seller,
+            price,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_license_listing(ctx: Context<CancelLicenseListing>) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+
+        if ctx.accounts.license.license_mint.is_some() {
+            let (Some(seller_token_account), Some(escrow)) = (
+                ctx.accounts.seller_license_token_account.as_ref(),
+                ctx.accounts.escrow_license_token_account.as_ref(),
+            ) else {
+                return err!(ErrorCode::MissingLicenseTokenAccounts);
+            };
+            let seeds = &[b"license_listing".as_ref(), listing.license.as_ref(), &[listing.bump]];
+            let signer = &[&seeds[..]];
+            release_license_token(
+                &ctx.accounts.token_program,
+                escrow,
+                seller_token_account,
+                &ctx.accounts.seller.to_account_info(),
+                &listing.to_account_info(),
+                signer,
+            )?;
+        }
+
+        emit!(LicenseListingCancelled {
+            listing: listing.key(),
+            license: listing.license,
+            seller: listing.seller,
+        });
+
+        Ok(())
+    }
+
+    /// Buys a listed license. The component's creator receives
+    /// `resale_royalty_bps` of the price and the platform its fee; the rest
+    /// goes to the seller.
+    pub fn purchase_license(ctx: Context<PurchaseLicense>, max_price: u64) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        let total_price = listing.price;
+        require!(total_price <= max_price, ErrorCode::PriceAboveMax);
+
+        let component = &ctx.accounts.component;
+        require!(!component.is_frozen, ErrorCode::ComponentFrozen);
+        let marketplace = &mut ctx.accounts.marketplace;
+        let platform_fee = marketplace.platform_fee(total_price);
+        let royalty = total_price
+            .checked_mul(component.resale_royalty_bps as u64)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap();
+        let seller_amount = total_price
+            .checked_sub(platform_fee)
+            .unwrap()
+            .checked_sub(royalty)
+            .unwrap();
+
+        let transfers = [
+            (&ctx.accounts.seller_flii_token_account, seller_amount),
+            (&ctx.accounts.creator_flii_token_account, royalty),
+            (&ctx.accounts.fee_vault, platform_fee),
+        ];
+        for (destination, amount) in transfers {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.buyer_flii_token_account.to_account_info(),
+                to: destination.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        // Hand the license to the buyer
+        if ctx.accounts.license.license_mint.is_some() {
+            let (Some(buyer_token_account), Some(escrow)) = (
+                ctx.accounts.buyer_license_token_account.as_ref(),
+                ctx.accounts.escrow_license_token_account.as_ref(),
+            ) else {
+                return err!(ErrorCode::MissingLicenseTokenAccounts);
+            };
+            let seeds = &[b"license_listing".as_ref(), listing.license.as_ref(), &[listing.bump]];
+            let signer = &[&seeds[..]];
+            release_license_token(
+                &ctx.accounts.token_program,
+                escrow,
+                buyer_token_account,
+                &ctx.accounts.seller.to_account_info(),
+                &listing.to_account_info(),
+                signer,
+            )?;
+        }
+
+        let seller = listing.seller;
+        let buyer = ctx.accounts.buyer.key();
+        let license = &mut ctx.accounts.license;
+        license.owner = buyer;
+
+        marketplace.total_license_resales += 1;
+        marketplace.total_resale_volume += total_price;
+        marketplace.total_fees_collected += platform_fee;
+
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.lifetime_earnings += royalty;
+
+        emit!(LicenseResold {
+            license: license.key(),
+            component_id: component.component_id.clone(),
+            seller,
+            buyer,
+            price: total_price,
+            creator_royalty: royalty,
+            platform_fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn delist_component(
+        ctx: Context<DelistComponent>,
+    ) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+        
+        component.is_active = false;
+        
+        Ok(())
+    }
+
+    /// Caps how many copies of a component can be sold in total and per
+    /// license holder. A supply cap can be lowered but never raised or
+    /// removed once set, so buyers of a limited edition keep their scarcity.
+    pub fn set_supply_limits(
+        ctx: Context<UpdateComponent>,
+        max_supply: Option<u64>,
+        max_per_wallet: Option<u32>,
+    ) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+
+        match (component.max_supply, max_supply) {
+            (_, Some(new_max)) => {
+                require!(
+                    new_max > 0 && new_max >= component.total_sales,
+                    ErrorCode::InvalidSupplyLimit
+                );
+                if let Some(current_max) = component.max_supply {
+                    require!(new_max <= current_max, ErrorCode::InvalidSupplyLimit);
+                }
+            }
+            (Some(_), None) => return err!(ErrorCode::InvalidSupplyLimit),
+            (None, None) => {}
+        }
+        require!(max_per_wallet != Some(0), ErrorCode::InvalidSupplyLimit);
+
+        component.max_supply = max_supply;
+        component.max_per_wallet = max_per_wallet;
+
+        emit!(SupplyLimitsUpdated {
+            component_id: component.component_id.clone(),
+            max_supply,
+            max_per_wallet,
+        });
+
+        Ok(())
+    }
+
+    /// Blocks purchases until `sale_start`. Zero makes the component
+    /// buyable immediately.
+    pub fn set_sale_start(ctx: Context<UpdateComponent>, sale_start: i64) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+        require!(sale_start >= 0, ErrorCode::InvalidSaleWindow);
+
+        component.sale_start = sale_start;
+
+        Ok(())
+    }
+
+    /// Schedules `sale_price` to apply to purchases made from `start`
+    /// (inclusive) to `end` (exclusive). Windows that have ended are pruned.
+    pub fn schedule_sale(
+        ctx: Context<UpdateComponent>,
+        start: i64,
+        end: i64,
+        sale_price: u64,
+    ) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+        require!(sale_price > 0, ErrorCode::InvalidPrice);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(start < end && end > now, ErrorCode::InvalidSaleWindow);
+
+        component.sale_windows.retain(|w| w.end > now);
+        require!(
+            component.sale_windows.iter().all(|w| end <= w.start || start >= w.end),
+            ErrorCode::SaleWindowOverlap
+        );
+        require!(
+            component.sale_windows.len() < Component::MAX_SALE_WINDOWS,
+            ErrorCode::TooManySaleWindows
+        );
+        component.sale_windows.push(SaleWindow { start, end, price: sale_price });
+
+        emit!(SaleScheduled {
+            component_id: component.component_id.clone(),
+            start,
+            end,
+            sale_price,
+        });
+
+        Ok(())
+    }
+
+    /// Removes the sale window starting at `start`.
+    pub fn cancel_sale(ctx: Context<UpdateComponent>, start: i64) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+
+        let index = component
+            .sale_windows
+            .iter()
+            .position(|w| w.start == start)
+            .ok_or(ErrorCode::SaleWindowNotFound)?;
+        component.sale_windows.remove(index);
+
+        Ok(())
+    }
+
+    /// Switches the component to Dutch-auction pricing, or back to its list
+    /// price and scheduled sales when `auction` is `None`.
+    pub fn set_dutch_auction(
+        ctx: Context<UpdateComponent>,
+        auction: Option<DutchAuction>,
+    ) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+
+        if let Some(auction) = &auction {
+            require!(
+                auction.floor_price > 0
+                    && auction.start_price > auction.floor_price
+                    && auction.decay_interval > 0
+                    && auction.decay_amount > 0,
+                ErrorCode::InvalidDutchAuction
+            );
+        }
+        component.dutch_auction = auction;
+
+        emit!(DutchAuctionUpdated {
+            component_id: component.component_id.clone(),
+            auction,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the share of every license resale paid to the component's
+    /// creator, in basis points.
+    pub fn set_resale_royalty(ctx: Context<UpdateComponent>, resale_royalty_bps: u16) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+        require!(
+            resale_royalty_bps <= Component::MAX_RESALE_ROYALTY_BPS,
+            ErrorCode::InvalidFeePercentage
+        );
+
+        component.resale_royalty_bps = resale_royalty_bps;
+
+        Ok(())
+    }
+
+    /// Chooses how future licenses of the component are issued. Licenses
+    /// already sold keep the form they were issued in. Offers and auctions
+    /// only issue receipts, so they are unavailable unless the mode is
+    /// `Receipt`.
+    pub fn set_license_mode(ctx: Context<UpdateComponent>, license_mode: LicenseMode) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+
+        component.license_mode = license_mode;
+
+        Ok(())
+    }
+
+    /// Offers ownership of a component to `new_creator`, who must accept it
+    /// with `accept_component_transfer`. Proposing again replaces the offer.
+    pub fn propose_component_transfer(
+        ctx: Context<ProposeComponentTransfer>,
+        new_creator: Pubkey,
+    ) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+        require!(new_creator != component.creator, ErrorCode::InvalidTransferRecipient);
+
+        component.pending_creator = Some(new_creator);
+
+        emit!(ComponentTransferProposed {
+            component_id: component.component_id.clone(),
+            creator: component.creator,
+            new_creator,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_component_transfer(ctx: Context<ProposeComponentTransfer>) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        require!(
+            component.creator == ctx.accounts.creator.key(),
+            ErrorCode::UnauthorizedCreator
+        );
+
+        component.pending_creator = None;
+
+        Ok(())
+    }
+
+    /// Completes a proposed transfer. The component keeps its address and
+    /// sales history; future sale proceeds go to the new creator's payout
+    /// wallet. An open auction pays the creator who started it, so it has
+    /// to be settled or cancelled first.
+    pub fn accept_component_transfer(ctx: Context<AcceptComponentTransfer>) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        let new_creator = ctx.accounts.new_creator.key();
+        require!(
+            component.pending_creator == Some(new_creator),
+            ErrorCode::NoPendingTransfer
+        );
+        require!(ctx.accounts.auction.data_is_empty(), ErrorCode::AuctionInProgress);
+
+        let new_creator_profile = &mut ctx.accounts.new_creator_profile;
+        if new_creator_profile.creator == Pubkey::default() {
+            new_creator_profile.initialize(new_creator, Clock::get()?.unix_timestamp);
+        }
+        require!(!new_creator_profile.is_banned, ErrorCode::CreatorBanned);
+        new_creator_profile.total_listings += 1;
+
+        let previous_creator_profile = &mut ctx.accounts.previous_creator_profile;
+        previous_creator_profile.total_listings =
+            previous_creator_profile.total_listings.saturating_sub(1);
+
+        let previous_creator = component.creator;
+        component.creator = new_creator;
+        component.pending_creator = None;
+
+        emit!(ComponentOwnershipTransferred {
+            component_id: component.component_id.clone(),
+            previous_creator,
+            new_creator,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the public profile of the signing creator. Creates the profile if
+    /// the creator has not listed anything yet, which is also how creators
+    /// with listings predating profiles get one.
+    pub fn update_creator_profile(
+        ctx: Context<UpdateCreatorProfile>,
+        handle: String,
+        profile_uri: String,
+        payout_wallet: Pubkey,
+    ) -> Result<()> {
+        require!(handle.len() <= CreatorProfile::MAX_HANDLE_LEN, ErrorCode::HandleTooLong);
+        require!(
+            profile_uri.len() <= CreatorProfile::MAX_PROFILE_URI_LEN,
+            ErrorCode::ProfileUriTooLong
+        );
+
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        if creator_profile.creator == Pubkey::default() {
+            creator_profile.initialize(ctx.accounts.creator.key(), Clock::get()?.unix_timestamp);
+        }
+        creator_profile.handle = handle;
+        creator_profile.profile_uri = profile_uri;
+        creator_profile.payout_wallet = payout_wallet;
+
+        emit!(CreatorProfileUpdated {
+            creator: creator_profile.creator,
+            handle: creator_profile.handle.clone(),
+            payout_wallet: creator_profile.payout_wallet,
+        });
+
+        Ok(())
+    }
+
+    /// Switches the creator's earnings vault on or off. While on, sales pay
+    /// the creator into the `[b"creator_vault", creator]` token account,
+    /// created on first use, instead of the payout wallet.
+    pub fn set_earnings_vault(ctx: Context<SetEarningsVault>, enabled: bool) -> Result<()> {
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.earnings_vault = ctx.accounts.earnings_vault.key();
+        creator_profile.earnings_vault_enabled = enabled;
+
+        emit!(EarningsVaultUpdated {
+            creator: creator_profile.creator,
+            earnings_vault: creator_profile.earnings_vault,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraws `amount` from the creator's earnings vault to any FLII
+    /// token account. Works whether or not the vault is still enabled.
+    pub fn claim_earnings(ctx: Context<ClaimEarnings>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.earnings_vault.amount,
+            ErrorCode::InsufficientEarnings
+        );
+
+        let creator = ctx.accounts.creator.key();
+        let seeds = &[
+            b"creator_profile".as_ref(),
+            creator.as_ref(),
+            &[ctx.bumps.creator_profile],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.earnings_vault.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.creator_profile.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.total_claimed += amount;
+
+        emit!(EarningsClaimed {
+            creator,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            total_claimed: creator_profile.total_claimed,
+        });
+
+        Ok(())
+    }
+
+    /// Grows the marketplace account to the current `Marketplace::SIZE`.
+    /// Accounts created by an older program version fail to deserialize
+    /// until migrated; fields added since then start zeroed.
+    pub fn migrate_marketplace(ctx: Context<MigrateMarketplace>) -> Result<()> {
+        let marketplace = &ctx.accounts.marketplace;
+
+        // The authority is the first field after the discriminator and has
+        // not moved since the first release.
+        let authority = {
+            let data = marketplace.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + 32 && data[..8] == Marketplace::DISCRIMINATOR,
+                ErrorCode::InvalidMarketplaceAccount
+            );
+            Pubkey::try_from(&data[8..40]).unwrap()
+        };
+        require_keys_eq!(
+            authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::UnauthorizedAuthority
+        );
+
+        let new_len = 8 + Marketplace::SIZE;
+        if marketplace.data_len() < new_len {
+            let rent_exempt = Rent::get()?.minimum_balance(new_len);
+            let shortfall = rent_exempt.saturating_sub(marketplace.lamports());
+            if shortfall > 0 {
+                let cpi_accounts = system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: marketplace.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.system_program.to_account_info();
+                let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+                system_program::transfer(cpi_ctx, shortfall)?;
+            }
+            marketplace.realloc(new_len, true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Delegates creator verification to `verifier`. Pass the default pubkey
+    /// to leave verification to the authority alone.
+    pub fn set_verifier(ctx: Context<SetVerifier>, verifier: Pubkey) -> Result<()> {
+        ctx.accounts.marketplace.verifier = verifier;
+
+        Ok(())
+    }
+
+    /// Replaces the holder fee tiers. Tiers must be ordered by strictly
+    /// increasing `min_balance`; an empty list restores the flat fee.
+    pub fn set_fee_tiers(ctx: Context<SetFeeTiers>, fee_tiers: Vec<FeeTier>) -> Result<()> {
+        require!(
+            fee_tiers.len() <= Marketplace::MAX_FEE_TIERS,
+            ErrorCode::TooManyFeeTiers
+        );
+        require!(
+            fee_tiers.windows(2).all(|pair| pair[0].min_balance < pair[1].min_balance),
+            ErrorCode::InvalidFeeTiers
+        );
+        require!(
+            fee_tiers.iter().all(|tier| tier.fee_percentage <= 1000),
+            ErrorCode::InvalidFeePercentage
+        ); // Max 10%
+
+        ctx.accounts.marketplace.fee_tiers = fee_tiers;
+
+        Ok(())
+    }
+
+    /// Sets the share of each component sale's platform fee that is burned
+    /// instead of sent to the treasury, in basis points.
+    pub fn set_burn_bps(ctx: Context<SetBurnBps>, burn_bps: u16) -> Result<()> {
+        require!(burn_bps <= 10000, ErrorCode::InvalidFeePercentage);
+        ctx.accounts.marketplace.burn_bps = burn_bps;
+
+        Ok(())
+    }
+
+    /// Creates the program-owned token account that collects platform fees.
+    pub fn initialize_fee_vault(_ctx: Context<InitializeFeeVault>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Pays `amount` out of the fee vault, across the treasury split when one
+    /// is configured (destination token accounts in `remaining_accounts`, in
+    /// leg order) or to the treasury wallet's token account otherwise.
+    pub fn withdraw_fees<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawFees<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.fee_vault.amount,
+            ErrorCode::InsufficientFees
+        );
+
+        let seeds = &[b"marketplace".as_ref(), &[ctx.bumps.marketplace]];
+        let signer = &[&seeds[..]];
+        let marketplace_info = ctx.accounts.marketplace.to_account_info();
+        let marketplace = &mut ctx.accounts.marketplace;
+
+        if !marketplace.treasury_split.is_empty() {
+            pay_treasury_split(
+                marketplace,
+                amount,
+                ctx.accounts.fee_vault.to_account_info(),
+                marketplace_info,
+                ctx.accounts.flii_token_mint.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.remaining_accounts,
+                signer,
+            )?;
+        } else {
+            let treasury = ctx
+                .accounts
+                .treasury_flii_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingTreasuryAccount)?;
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.fee_vault.to_account_info(),
+                to: treasury.to_account_info(),
+                authority: marketplace_info,
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        marketplace.total_fees_withdrawn += amount;
+
+        emit!(FeesWithdrawn {
+            amount,
+            total_fees_collected: marketplace.total_fees_collected,
+            total_fees_withdrawn: marketplace.total_fees_withdrawn,
+        });
+
+        Ok(())
+    }
+
+    /// Replaces the treasury split. Fees collect in the fee vault and the
+    /// split is applied when they are withdrawn. Legs must add up to 10000
+    /// basis points; an empty split sends withdrawals to `treasury_wallet`.
+    /// The destination token account of every transfer leg is passed in
+    /// `remaining_accounts`, in leg order, so its mint can be checked.
+    pub fn set_treasury_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetTreasurySplit<'info>>,
+        legs: Vec<TreasuryLeg>,
+    ) -> Result<()> {
+        require!(
+            legs.len() <= Marketplace::MAX_TREASURY_LEGS,
+            ErrorCode::TooManyTreasuryLegs
+        );
+        if !legs.is_empty() {
+            let total_bps = legs.iter().map(|leg| leg.bps as u32).sum::<u32>();
+            require!(total_bps == 10000, ErrorCode::InvalidTreasurySplit);
+        }
+
+        let marketplace = &mut ctx.accounts.marketplace;
+        let mut destinations = ctx.remaining_accounts.iter();
+        for leg in legs.iter().filter(|leg| leg.kind != TreasuryLegKind::Burn) {
+            let account_info = destinations
+                .next()
+                .ok_or(ErrorCode::MissingTreasuryAccount)?;
+            require_keys_eq!(
+                account_info.key(),
+                leg.destination,
+                ErrorCode::InvalidTreasuryAccount
+            );
+            let destination = Account::<TokenAccount>::try_from(account_info)?;
+            require_keys_eq!(
+                destination.mint,
+                marketplace.flii_token_mint,
+                ErrorCode::InvalidTreasuryAccount
+            );
+        }
+
+        marketplace.treasury_split = legs;
+
+        Ok(())
+    }
+
+    pub fn verify_creator(ctx: Context<SetCreatorVerification>) -> Result<()> {
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.verified = true;
+        creator_profile.verified_at = Clock::get()?.unix_timestamp;
+
+        emit!(CreatorVerified {
+            creator: creator_profile.creator,
+            verifier: ctx.accounts.verifier.key(),
+            verified_at: creator_profile.verified_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn unverify_creator(ctx: Context<SetCreatorVerification>) -> Result<()> {
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.verified = false;
+        creator_profile.verified_at = 0;
+
+        emit!(CreatorUnverified {
+            creator: creator_profile.creator,
+            verifier: ctx.accounts.verifier.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn add_moderator(ctx: Context<UpdateModerators>, moderator: Pubkey) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        require!(
+            !marketplace.moderators.contains(&moderator),
+            ErrorCode::ModeratorAlreadyAdded
+        );
+        require!(
+            marketplace.moderators.len() < Marketplace::MAX_MODERATORS,
+            ErrorCode::TooManyModerators
+        );
+        marketplace.moderators.push(moderator);
+
+        Ok(())
+    }
+
+    pub fn remove_moderator(ctx: Context<UpdateModerators>, moderator: Pubkey) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        let index = marketplace
+            .moderators
+            .iter()
+            .position(|m| *m == moderator)
+            .ok_or(ErrorCode::ModeratorNotFound)?;
+        marketplace.moderators.remove(index);
+
+        Ok(())
+    }
+
+    /// Blocks purchases of a component until a moderator unfreezes it.
+    /// `reason_code` is an off-chain policy code recorded for the creator.
+    pub fn freeze_component(ctx: Context<ModerateComponent>, reason_code: u16) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        component.is_frozen = true;
+        component.freeze_reason = reason_code;
+
+        emit!(ComponentFrozen {
+            component_id: component.component_id.clone(),
+            moderator: ctx.accounts.moderator.key(),
+            reason_code,
+        });
+
+        Ok(())
+    }
+
+    pub fn unfreeze_component(ctx: Context<ModerateComponent>) -> Result<()> {
+        let component = &mut ctx.accounts.component;
+        component.is_frozen = false;
+        component.freeze_reason = 0;
+
+        emit!(ComponentUnfrozen {
+            component_id: component.component_id.clone(),
+            moderator: ctx.accounts.moderator.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Blocks a creator from listing new components. Creates the creator's
+    /// profile if needed so wallets can be banned before their first listing.
+    pub fn ban_creator(ctx: Context<ModerateCreator>, reason_code: u16) -> Result<()> {
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        if creator_profile.creator == Pubkey::default() {
+            creator_profile.initialize(ctx.accounts.creator.key(), Clock::get()?.unix_timestamp);
+        }
+        creator_profile.is_banned = true;
+        creator_profile.ban_reason = reason_code;
+
+        emit!(CreatorBanned {
+            creator: creator_profile.creator,
+            moderator: ctx.accounts.moderator.key(),
+            reason_code,
+        });
+
+        Ok(())
+    }
+
+    pub fn unban_creator(ctx: Context<UnbanCreator>) -> Result<()> {
+        let creator_profile = &mut ctx.accounts.creator_profile;
+        creator_profile.is_banned = false;
+        creator_profile.ban_reason = 0;
+
+        emit!(CreatorUnbanned {
+            creator: creator_profile.creator,
+            moderator: ctx.accounts.moderator.key(),
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Marketplace::SIZE,
+        seeds = [b"marketplace"],
+        bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        constraint = flii_token_mint.key() == FLII_TOKEN_MINT.parse::<Pubkey>().unwrap()
+    )]
+    pub flii_token_mint: Account<'info, Mint>,
+    /// CHECK: Treasury wallet for collecting fees
+    pub treasury_wallet: AccountInfo<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(component_id: String)]
+pub struct ListComponent<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Component::SIZE,
+        seeds = [b"component", creator.key().as_ref(), component_id.as_bytes()],
+        bump
+    )]
+    pub component: Account<'info, Component>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + CreatorProfile::SIZE,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(component_id: String)]
+pub struct MigrateComponent<'info> {
+    /// CHECK: Read by hand because the legacy layout fails `Account` deserialization
+    #[account(
+        mut,
+        seeds = [b"component", component_id.as_bytes()],
+        bump,
+        owner = crate::ID
+    )]
+    pub legacy_component: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Component::SIZE,
+        seeds = [b"component", creator.key().as_ref(), component_id.as_bytes()],
+        bump
+    )]
+    pub component: Account<'info, Component>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + CreatorProfile::SIZE,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Option<Pubkey>)]
+pub struct PurchaseComponent<'info> {
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", component.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        constraint = flii_token_mint.key() == FLII_TOKEN_MINT.parse::<Pubkey>().unwrap()
+    )]
+    pub flii_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_flii_token_account.mint == flii_token_mint.key(),
+        constraint = buyer_flii_token_account.owner == buyer.key()
+    )]
+    pub buyer_flii_token_account: Account<'info, TokenAccount>,
+    // Buyer's FLII stake, counted towards the fee tier when passed
+    #[account(
+        constraint = buyer_stake_position.owner == buyer.key() @ ErrorCode::InvalidStakePosition
+    )]
+    pub buyer_stake_position: Option<Box<Account<'info, StakePosition>>>,
+    #[account(
+        mut,
+        constraint = creator_flii_token_account.mint == flii_token_mint.key(),
+        constraint = creator_profile.is_payout_account(&creator_flii_token_account) @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub creator_flii_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + License::SIZE,
+        seeds = [b"license", component.key().as_ref(), &component.total_sales.to_le_bytes()],
+        bump
+    )]
+    pub license: Account<'info, License>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + WalletPurchases::SIZE,
+        seeds = [
+            b"wallet_purchases",
+            component.key().as_ref(),
+            recipient.unwrap_or(buyer.key()).as_ref()
+        ],
+        bump
+    )]
+    pub wallet_purchases: Account<'info, WalletPurchases>,
+    // License token accounts, required when the component issues
+    // transferable licenses and omitted otherwise.
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"license_mint", license.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = license_mint
+    )]
+    pub license_mint: Option<Box<Account<'info, Mint>>>,
+    /// CHECK: Owner of the license token, checked against the recipient
+    #[account(address = recipient.unwrap_or(buyer.key()))]
+    pub license_recipient: Option<UncheckedAccount<'info>>,
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = license_mint,
+        associated_token::authority = license_recipient
+    )]
+    pub license_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    /// CHECK: Metaplex metadata PDA of the license mint, created by CPI
+    #[account(mut)]
+    pub license_metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: Metaplex token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+    // Soulbound license accounts, required when the component issues
+    // soulbound licenses and omitted otherwise. The mint is created with the
+    // Token-2022 non-transferable extension in the handler.
+    /// CHECK: Uninitialized license mint PDA, created in the handler
+    #[account(
+        mut,
+        seeds = [b"license_mint", license.key().as_ref()],
+        bump
+    )]
+    pub soulbound_mint: Option<UncheckedAccount<'info>>,
+    /// CHECK: Recipient's Token-2022 associated token account, created in the handler
+    #[account(mut)]
+    pub soulbound_token_account: Option<UncheckedAccount<'info>>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PurchaseComponent<'info> {
+    /// Mints the single token of a transferable license to the recipient,
+    /// attaches Metaplex metadata pointing at the component and removes the
+    /// mint authority so no second token can exist.
+    fn mint_license_token(
+        &self,
+        metadata_uri: &str,
+        edition: u64,
+        mint_bump: u8,
+    ) -> Result<Pubkey> {
+        let (
+            Some(license_mint),
+            Some(license_token_account),
+            Some(license_metadata),
+            Some(token_metadata_program),
+        ) = (
+            self.license_mint.as_ref(),
+            self.license_token_account.as_ref(),
+            self.license_metadata.as_ref(),
+            self.token_metadata_program.as_ref(),
+        )
+        else {
+            return err!(ErrorCode::MissingLicenseTokenAccounts);
+        };
+
+        let license_key = self.license.key();
+        let seeds = &[b"license_mint".as_ref(), license_key.as_ref(), &[mint_bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: license_mint.to_account_info(),
+            to: license_token_account.to_account_info(),
+            authority: license_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token::mint_to(cpi_ctx, 1)?;
+
+        let (metadata_address, _) = Metadata::find_pda(&license_mint.key());
+        require_keys_eq!(
+            license_metadata.key(),
+            metadata_address,
+            ErrorCode::MissingLicenseTokenAccounts
+        );
+        let mint_info = license_mint.to_account_info();
+        let payer_info = self.buyer.to_account_info();
+        let metadata_info = license_metadata.to_account_info();
+        let program_info = token_metadata_program.to_account_info();
+        let system_program_info = self.system_program.to_account_info();
+        CreateMetadataAccountV3Cpi::new(
+            &program_info,
+            CreateMetadataAccountV3CpiAccounts {
+                metadata: &metadata_info,
+                mint: &mint_info,
+                mint_authority: &mint_info,
+                payer: &payer_info,
+                update_authority: (&mint_info, true),
+                system_program: &system_program_info,
+                rent: None,
+            },
+            CreateMetadataAccountV3InstructionArgs {
+                data: DataV2 {
+                    name: format!("FLII License #{}", edition),
+                    symbol: "FLIILIC".to_string(),
+                    uri: metadata_uri.to_string(),
+                    seller_fee_basis_points: 0,
+                    creators: None,
+                    collection: None,
+                    uses: None,
+                },
+                is_mutable: false,
+                collection_details: None,
+            },
+        )
+        .invoke_signed(signer)?;
+
+        let cpi_accounts_authority = SetAuthority {
+            current_authority: license_mint.to_account_info(),
+            account_or_mint: license_mint.to_account_info(),
+        };
+        let cpi_ctx_authority = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts_authority,
+            signer,
+        );
+        token::set_authority(cpi_ctx_authority, AuthorityType::MintTokens, None)?;
+
+        Ok(license_mint.key())
+    }
+
+    /// Mints the single token of a soulbound license to the recipient from
+    /// a Token-2022 mint with the non-transferable extension, then removes
+    /// the mint authority.
+    fn mint_soulbound_token(&self, mint_bump: u8) -> Result<Pubkey> {
+        let (
+            Some(soulbound_mint),
+            Some(soulbound_token_account),
+            Some(license_recipient),
+            Some(token_2022_program),
+        ) = (
+            self.soulbound_mint.as_ref(),
+            self.soulbound_token_account.as_ref(),
+            self.license_recipient.as_ref(),
+            self.token_2022_program.as_ref(),
+        )
+        else {
+            return err!(ErrorCode::MissingLicenseTokenAccounts);
+        };
+
+        let license_key = self.license.key();
+        let seeds = &[b"license_mint".as_ref(), license_key.as_ref(), &[mint_bump]];
+        let signer = &[&seeds[..]];
+        let mint_key = soulbound_mint.key();
+        let mint_info = soulbound_mint.to_account_info();
+        let token_2022_info = token_2022_program.to_account_info();
+
+        // Create the mint with room for the non-transferable extension
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::NonTransferable,
+        ])?;
+        let cpi_accounts_create = system_program::CreateAccount {
+            from: self.buyer.to_account_info(),
+            to: mint_info.clone(),
+        };
+        let cpi_ctx_create = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            cpi_accounts_create,
+            signer,
+        );
+        system_program::create_account(
+            cpi_ctx_create,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &token_2022::ID,
+        )?;
+
+        let init_non_transferable = spl_token_2022::instruction::initialize_non_transferable_mint(
+            &token_2022::ID,
+            &mint_key,
+        )?;
+        solana_program::program::invoke(
+            &init_non_transferable,
+            &[mint_info.clone(), token_2022_info.clone()],
+        )?;
+
+        let cpi_accounts_init = token_2022::InitializeMint2 { mint: mint_info.clone() };
+        let cpi_ctx_init = CpiContext::new(token_2022_info.clone(), cpi_accounts_init);
+        token_2022::initialize_mint2(cpi_ctx_init, 0, &mint_key, None)?;
+
+        // Create the recipient's token account and mint the license token
+        require_keys_eq!(
+            soulbound_token_account.key(),
+            associated_token::get_associated_token_address_with_program_id(
+                &license_recipient.key(),
+                &mint_key,
+                &token_2022::ID,
+            ),
+            ErrorCode::MissingLicenseTokenAccounts
+        );
+        let cpi_accounts_ata = associated_token::Create {
+            payer: self.buyer.to_account_info(),
+            associated_token: soulbound_token_account.to_account_info(),
+            authority: license_recipient.to_account_info(),
+            mint: mint_info.clone(),
+            system_program: self.system_program.to_account_info(),
+            token_program: token_2022_info.clone(),
+        };
+        let cpi_ctx_ata = CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            cpi_accounts_ata,
+        );
+        associated_token::create(cpi_ctx_ata)?;
+
+        let cpi_accounts_mint = token_2022::MintTo {
+            mint: mint_info.clone(),
+            to: soulbound_token_account.to_account_info(),
+            authority: mint_info.clone(),
+        };
+        let cpi_ctx_mint =
+            CpiContext::new_with_signer(token_2022_info.clone(), cpi_accounts_mint, signer);
+        token_2022::mint_to(cpi_ctx_mint, 1)?;
+
+        let cpi_accounts_authority = token_2022::SetAuthority {
+            current_authority: mint_info.clone(),
+            account_or_mint: mint_info,
+        };
+        let cpi_ctx_authority =
+            CpiContext::new_with_signer(token_2022_info, cpi_accounts_authority, signer);
+        token_2022::set_authority(
+            cpi_ctx_authority,
+            spl_token_2022::instruction::AuthorityType::MintTokens,
+            None,
+        )?;
+
+        Ok(mint_key)
+    }
+}
+
+/// Returns an offer's escrow to its buyer and closes the vault. The offer
+/// account itself is closed by the `close` constraint.
+fn refund_offer(ctx: &Context<CloseOffer>) -> Result<()> {
+    let offer = &ctx.accounts.offer;
+    let seeds = &[
+        b"offer".as_ref(),
+        offer.component.as_ref(),
+        offer.buyer.as_ref(),
+        &[offer.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let refund_accounts = Transfer {
+        from: ctx.accounts.offer_vault.to_account_info(),
+        to: ctx.accounts.buyer_flii_token_account.to_account_info(),
+        authority: offer.to_account_info(),
+    };
+    let refund_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        refund_accounts,
+        signer,
+    );
+    token::transfer(refund_ctx, ctx.accounts.offer_vault.amount)?;
+
+    let close_accounts = CloseAccount {
+        account: ctx.accounts.offer_vault.to_account_info(),
+        destination: ctx.accounts.buyer.to_account_info(),
+        authority: offer.to_account_info(),
+    };
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        close_accounts,
+        signer,
+    );
+    token::close_account(close_ctx)
+}
+
+/// Splits `amount` of platform fees across the marketplace's treasury legs.
+/// Transfer legs take their destination token accounts from
+/// `remaining_accounts` in leg order; burn legs take none. The last leg
+/// receives any rounding remainder.
+#[allow(clippy::too_many_arguments)]
+fn pay_treasury_split<'info>(
+    marketplace: &mut Marketplace,
+    amount: u64,
+    from: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    flii_token_mint: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let legs = marketplace.treasury_split.clone();
+    let mut destinations = remaining_accounts.iter();
+    let mut remaining = amount;
+
+    for (index, leg) in legs.iter().enumerate() {
+        let leg_amount = if index == legs.len() - 1 {
+            remaining
+        } else {
+            amount
+                .checked_mul(leg.bps as u64)
+                .unwrap()
+                .checked_div(10000)
+                .unwrap()
+        };
+        remaining = remaining.checked_sub(leg_amount).unwrap();
+
+        let destination = if leg.kind == TreasuryLegKind::Burn {
+            None
+        } else {
+            let account_info = destinations
+                .next()
+                .ok_or(ErrorCode::MissingTreasuryAccount)?;
+            require_keys_eq!(
+                account_info.key(),
+                leg.destination,
+                ErrorCode::InvalidTreasuryAccount
+            );
+            Some(account_info)
+        };
+        if leg_amount == 0 {
+            continue;
+        }
+
+        match destination {
+            None => {
+                let cpi_accounts = Burn {
+                    mint: flii_token_mint.clone(),
+                    from: from.clone(),
+                    authority: authority.clone(),
+                };
+                let cpi_ctx =
+                    CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+                token::burn(cpi_ctx, leg_amount)?;
+                marketplace.total_burned += leg_amount;
+            }
+            Some(account_info) => {
+                let cpi_accounts = Transfer {
+                    from: from.clone(),
+                    to: account_info.clone(),
+                    authority: authority.clone(),
+                };
+                let cpi_ctx =
+                    CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+                token::transfer(cpi_ctx, leg_amount)?;
+            }
+        }
+
+        emit!(TreasuryLegPaid {
+            kind: leg.kind,
+            destination: leg.destination,
+            amount: leg_amount,
+        });
+    }
+
+    Ok(())
+}
+
+/// Moves a tokenized license out of a resale escrow and closes the escrow,
+/// returning its rent to the seller.
+fn release_license_token<'info>(
+    token_program: &Program<'info, Token>,
+    escrow: &Account<'info, TokenAccount>,
+    destination: &Account<'info, TokenAccount>,
+    seller: &AccountInfo<'info>,
+    listing: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from: escrow.to_account_info(),
+        to: destination.to_account_info(),
+        authority: listing.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::transfer(cpi_ctx, 1)?;
+
+    let close_accounts = CloseAccount {
+        account: escrow.to_account_info(),
+        destination: seller.clone(),
+        authority: listing.clone(),
+    };
+    let close_ctx =
+        CpiContext::new_with_signer(token_program.to_account_info(), close_accounts, signer);
+    token::close_account(close_ctx)
+}
+
+#[derive(Accounts)]
+pub struct ListLicense<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + LicenseListing::SIZE,
+        seeds = [b"license_listing", license.key().as_ref()],
+        bump,
+        // Checked here so it runs before the escrow is created for the mint
+        constraint = !matches!(&license_mint, Some(mint) if *mint.owner == token_2022::ID)
+            @ ErrorCode::LicenseNotTransferable
+    )]
+    pub listing: Account<'info, LicenseListing>,
+    pub license: Account<'info, License>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    // License token accounts, required for tokenized licenses only
+    /// CHECK: Soulbound Token-2022 mints are rejected above; the escrow
+    /// initialization checks it is an SPL token mint
+    pub license_mint: Option<UncheckedAccount<'info>>,
+    #[account(
+        mut,
+        constraint = seller_license_token_account.owner == seller.key()
+    )]
+    pub seller_license_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        init,
+        payer = seller,
+        seeds = [b"license_escrow", license.key().as_ref()],
+        bump,
+        token::mint = license_mint,
+        token::authority = listing
+    )]
+    pub escrow_license_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLicenseListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"license_listing", license.key().as_ref()],
+        bump = listing.bump,
+        has_one = seller @ ErrorCode::UnauthorizedSeller,
+        close = seller
+    )]
+    pub listing: Account<'info, LicenseListing>,
+    pub license: Account<'info, License>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    #[account(
+        mut,
+        constraint = seller_license_token_account.owner == seller.key(),
+        constraint = Some(seller_license_token_account.mint) == license.license_mint
+    )]
+    pub seller_license_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        mut,
+        seeds = [b"license_escrow", license.key().as_ref()],
+        bump
+    )]
+    pub escrow_license_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseLicense<'info> {
+    #[account(
+        mut,
+        seeds = [b"license_listing", license.key().as_ref()],
+        bump = listing.bump,
+        close = seller
+    )]
+    pub listing: Box<Account<'info, LicenseListing>>,
+    #[account(mut, has_one = component)]
+    pub license: Box<Account<'info, License>>,
+    pub component: Box<Account<'info, Component>>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", component.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Box<Account<'info, CreatorProfile>>,
+    #[account(mut)]
+    pub marketplace: Box<Account<'info, Marketplace>>,
+    /// CHECK: Receives the listing and escrow rent, checked against the listing
+    #[account(mut, address = listing.seller)]
+    pub seller: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = buyer_flii_token_account.owner == buyer.key()
+    )]
+    pub buyer_flii_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = seller_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = seller_flii_token_account.owner == listing.seller
+    )]
+    pub seller_flii_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = creator_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = creator_profile.is_payout_account(&creator_flii_token_account) @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub creator_flii_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+    // License token accounts, required for tokenized licenses only
+    #[account(constraint = Some(license_mint.key()) == license.license_mint @ ErrorCode::InvalidLicenseToken)]
+    pub license_mint: Option<Box<Account<'info, Mint>>>,
+    #[account(
+        mut,
+        seeds = [b"license_escrow", license.key().as_ref()],
+        bump
+    )]
+    pub escrow_license_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = license_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_license_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MakeOffer<'info> {
+    pub component: Account<'info, Component>,
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Offer::SIZE,
+        seeds = [b"offer", component.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"offer_vault", offer.key().as_ref()],
+        bump,
+        token::mint = flii_token_mint,
+        token::authority = offer
+    )]
+    pub offer_vault: Account<'info, TokenAccount>,
+    #[account(
+        constraint = flii_token_mint.key() == FLII_TOKEN_MINT.parse::<Pubkey>().unwrap()
+    )]
+    pub flii_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_flii_token_account.mint == flii_token_mint.key(),
+        constraint = buyer_flii_token_account.owner == buyer.key()
+    )]
+    pub buyer_flii_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CloseOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"offer", offer.component.as_ref(), offer.buyer.as_ref()],
+        bump = offer.bump,
+        close = buyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        seeds = [b"offer_vault", offer.key().as_ref()],
+        bump
+    )]
+    pub offer_vault: Account<'info, TokenAccount>,
+    /// CHECK: Receives the offer and vault rent, checked against the offer
+    #[account(mut, address = offer.buyer)]
+    pub buyer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = buyer_flii_token_account.mint == offer_vault.mint,
+        constraint = buyer_flii_token_account.owner == offer.buyer
+    )]
+    pub buyer_flii_token_account: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"offer", component.key().as_ref(), offer.buyer.as_ref()],
+        bump = offer.bump,
+        close = buyer
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        seeds = [b"offer_vault", offer.key().as_ref()],
+        bump
+    )]
+    pub offer_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", component.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    /// CHECK: Receives the offer and vault rent, checked against the offer
+    #[account(mut, address = offer.buyer)]
+    pub buyer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = buyer_flii_token_account.mint == offer_vault.mint,
+        constraint = buyer_flii_token_account.owner == offer.buyer
+    )]
+    pub buyer_flii_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        constraint = creator_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = creator_profile.is_payout_account(&creator_flii_token_account) @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub creator_flii_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + License::SIZE,
+        seeds = [b"license", component.key().as_ref(), &component.total_sales.to_le_bytes()],
+        bump
+    )]
+    pub license: Account<'info, License>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + WalletPurchases::SIZE,
+        seeds = [b"wallet_purchases", component.key().as_ref(), offer.buyer.as_ref()],
+        bump
+    )]
+    pub wallet_purchases: Account<'info, WalletPurchases>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    pub component: Account<'info, Component>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Auction::SIZE,
+        seeds = [b"auction", component.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"auction_escrow", auction.key().as_ref()],
+        bump,
+        token::mint = flii_token_mint,
+        token::authority = auction
+    )]
+    pub escrow_flii_token_account: Account<'info, TokenAccount>,
+    #[account(
+        constraint = flii_token_mint.key() == FLII_TOKEN_MINT.parse::<Pubkey>().unwrap()
+    )]
+    pub flii_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.component.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [b"auction_escrow", auction.key().as_ref()],
+        bump
+    )]
+    pub escrow_flii_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(
+        mut,
+        constraint = bidder_flii_token_account.mint == escrow_flii_token_account.mint,
+        constraint = bidder_flii_token_account.owner == bidder.key()
+    )]
+    pub bidder_flii_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = previous_bidder_flii_token_account.mint == escrow_flii_token_account.mint,
+        constraint = Some(previous_bidder_flii_token_account.owner) == auction.highest_bidder
+    )]
+    pub previous_bidder_flii_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", component.key().as_ref()],
+        bump = auction.bump,
+        close = creator
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [b"auction_escrow", auction.key().as_ref()],
+        bump
+    )]
+    pub escrow_flii_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", component.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    /// CHECK: Receives the auction and escrow rent, checked against the auction
+    #[account(mut, address = auction.creator)]
+    pub creator: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = creator_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = creator_profile.is_payout_account(&creator_flii_token_account) @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub creator_flii_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = bidder_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = Some(bidder_flii_token_account.owner) == auction.highest_bidder
+    )]
+    pub bidder_flii_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = settler,
+        space = 8 + License::SIZE,
+        seeds = [b"license", component.key().as_ref(), &component.total_sales.to_le_bytes()],
+        bump
+    )]
+    pub license: Option<Account<'info, License>>,
+    // Winner's purchase count, required whenever the auction has bids
+    #[account(
+        init_if_needed,
+        payer = settler,
+        space = 8 + WalletPurchases::SIZE,
+        seeds = [b"wallet_purchases", component.key().as_ref(), auction.bidder_seed()],
+        bump
+    )]
+    pub wallet_purchases: Option<Account<'info, WalletPurchases>>,
+    #[account(mut)]
+    pub settler: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.component.as_ref()],
+        bump = auction.bump,
+        has_one = creator @ ErrorCode::UnauthorizedCreator,
+        close = creator
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [b"auction_escrow", auction.key().as_ref()],
+        bump
+    )]
+    pub escrow_flii_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    // Receives FLII sent to the escrow directly, required if there is any
+    #[account(
+        mut,
+        constraint = creator_flii_token_account.mint == escrow_flii_token_account.mint,
+        constraint = creator_flii_token_account.owner == creator.key()
+    )]
+    pub creator_flii_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateNFTListing<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + NFTListing::SIZE,
+        seeds = [b"nft_listing", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, NFTListing>,
+    #[account(
+        init,
+        payer = seller,
+        seeds = [b"nft_escrow", nft_mint.key().as_ref()],
+        bump,
+        token::mint = nft_mint,
+        token::authority = listing
+    )]
+    pub escrow_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        constraint = nft_mint.decimals == 0 && nft_mint.supply == 1 @ ErrorCode::InvalidNFT
+    )]
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = seller_nft_account.mint == nft_mint.key(),
+        constraint = seller_nft_account.owner == seller.key()
+    )]
+    pub seller_nft_account: Account<'info, TokenAccount>,
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CancelNFTListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"nft_listing", listing.nft_mint.as_ref()],
+        bump = listing.bump,
+        has_one = seller @ ErrorCode::UnauthorizedSeller,
+        close = seller
+    )]
+    pub listing: Account<'info, NFTListing>,
+    #[account(
+        mut,
+        seeds = [b"nft_escrow", listing.nft_mint.as_ref()],
+        bump
+    )]
+    pub escrow_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_nft_account.mint == listing.nft_mint,
+        constraint = seller_nft_account.owner == seller.key()
+    )]
+    pub seller_nft_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseNFT<'info> {
+    #[account(
+        mut,
+        seeds = [b"nft_listing", nft_mint.key().as_ref()],
+        bump = listing.bump,
+        has_one = nft_mint,
+        close = seller
+    )]
+    pub listing: Account<'info, NFTListing>,
+    #[account(
+        mut,
+        seeds = [b"nft_escrow", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub escrow_nft_account: Account<'info, TokenAccount>,
+    pub nft_mint: Account<'info, Mint>,
+    /// CHECK: Metaplex metadata PDA of the mint, parsed in the handler when it exists
+    #[account(
+        seeds = [b"metadata", mpl_token_metadata::ID.as_ref(), nft_mint.key().as_ref()],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub marketplace: Account<'info, Marketplace>,
+    /// CHECK: Receives the listing and escrow rent, checked against the listing
+    #[account(mut, address = listing.seller)]
+    pub seller: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        mut,
+        constraint = buyer_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = buyer_flii_token_account.owner == buyer.key()
+    )]
+    pub buyer_flii_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_nft_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = seller_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = seller_flii_token_account.owner == listing.seller
+    )]
+    pub seller_flii_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelistComponent<'info> {
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateComponent<'info> {
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeComponentTransfer<'info> {
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptComponentTransfer<'info> {
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", component.creator.as_ref()],
+        bump
+    )]
+    pub previous_creator_profile: Account<'info, CreatorProfile>,
+    /// CHECK: Auction PDA of the component, which must not exist
+    #[account(
+        seeds = [b"auction", component.key().as_ref()],
+        bump
+    )]
+    pub auction: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = new_creator,
+        space = 8 + CreatorProfile::SIZE,
+        seeds = [b"creator_profile", new_creator.key().as_ref()],
+        bump
+    )]
+    pub new_creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub new_creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCreatorProfile<'info> {
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + CreatorProfile::SIZE,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetEarningsVault<'info> {
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(address = marketplace.flii_token_mint @ ErrorCode::InvalidTokenMint)]
+    pub flii_token_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        seeds = [b"creator_vault", creator.key().as_ref()],
+        bump,
+        token::mint = flii_token_mint,
+        token::authority = creator_profile
+    )]
+    pub earnings_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimEarnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    #[account(
+        mut,
+        seeds = [b"creator_vault", creator.key().as_ref()],
+        bump
+    )]
+    pub earnings_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = destination.mint == earnings_vault.mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarketplace<'info> {
+    /// CHECK: Read by hand because an outdated layout fails `Account` deserialization
+    #[account(
+        mut,
+        seeds = [b"marketplace"],
+        bump,
+        owner = crate::ID
+    )]
+    pub marketplace: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVerifier<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAuthority)]
+    pub marketplace: Account<'info, Marketplace>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeTiers<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAuthority)]
+    pub marketplace: Account<'info, Marketplace>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetBurnBps<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAuthority)]
+    pub marketplace: Account<'info, Marketplace>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(has_one = authority @ ErrorCode::UnauthorizedAuthority)]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(address = marketplace.flii_token_mint @ ErrorCode::InvalidTokenMint)]
+    pub flii_token_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"fee_vault"],
+        bump,
+        token::mint = flii_token_mint,
+        token::authority = marketplace
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"marketplace"],
+        bump,
+        has_one = authority @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault"],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    // Burned from by burn legs of the treasury split
+    #[account(mut, address = marketplace.flii_token_mint @ ErrorCode::InvalidTokenMint)]
+    pub flii_token_mint: Account<'info, Mint>,
+    // Required when no treasury split is configured
+    #[account(
+        mut,
+        constraint = treasury_flii_token_account.mint == marketplace.flii_token_mint,
+        constraint = treasury_flii_token_account.owner == marketplace.treasury_wallet
+    )]
+    pub treasury_flii_token_account: Option<Account<'info, TokenAccount>>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetTreasurySplit<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAuthority)]
+    pub marketplace: Account<'info, Marketplace>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCreatorVerification<'info> {
+    #[account(
+        constraint = marketplace.can_verify(&verifier.key()) @ ErrorCode::UnauthorizedVerifier
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", creator_profile.creator.as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    pub verifier: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateModerators<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::UnauthorizedAuthority)]
+    pub marketplace: Account<'info, Marketplace>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ModerateComponent<'info> {
+    #[account(
+        constraint = marketplace.is_moderator(&moderator.key()) @ ErrorCode::UnauthorizedModerator
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(mut)]
+    pub component: Account<'info, Component>,
+    pub moderator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ModerateCreator<'info> {
+    #[account(
+        constraint = marketplace.is_moderator(&moderator.key()) @ ErrorCode::UnauthorizedModerator
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        init_if_needed,
+        payer = moderator,
+        space = 8 + CreatorProfile::SIZE,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    /// CHECK: Only used to derive the creator profile address
+    pub creator: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub moderator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnbanCreator<'info> {
+    #[account(
+        constraint = marketplace.is_moderator(&moderator.key()) @ ErrorCode::UnauthorizedModerator
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    #[account(
+        mut,
+        seeds = [b"creator_profile", creator.key().as_ref()],
+        bump,
+        constraint = creator_profile.is_banned @ ErrorCode::CreatorNotBanned
+    )]
+    pub creator_profile: Account<'info, CreatorProfile>,
+    /// CHECK: Only used to derive the creator profile address
+    pub creator: UncheckedAccount<'info>,
+    pub moderator: Signer<'info>,
+}
+
+#[account]
+pub struct Marketplace {
+    pub authority: Pubkey,
+    pub fee_percentage: u16,
+    pub total_volume: u64,
+    pub total_listings: u64,
+    pub total_sales: u64,
+    pub flii_token_mint: Pubkey,
+    pub treasury_wallet: Pubkey,
+    pub verifier: Pubkey,  // Delegated creator verifier, default pubkey when unset
+    pub moderators: Vec<Pubkey>,
+    pub fee_tiers: Vec<FeeTier>,  // Discounted fees for FLII holders
+    pub burn_bps: u16,  // Share of component sale fees burned
+    pub total_burned: u64,
+    pub treasury_split: Vec<TreasuryLeg>,  // Empty sends fees to treasury_wallet
+    pub total_fees_collected: u64,  // Platform fees paid into the fee vault
+    pub total_fees_withdrawn: u64,
+    pub total_nft_sales: u64,  // Secondary NFT sales, not counted in total_sales
+    pub total_resale_volume: u64,  // Secondary sale volume, not counted in total_volume
+    pub total_license_resales: u64,  // Not counted in total_sales
+}
+
+impl Marketplace {
+    pub const MAX_MODERATORS: usize = 5;
+    pub const MAX_FEE_TIERS: usize = 5;
+    pub const MAX_TREASURY_LEGS: usize = 5;
+    pub const SIZE: usize = 32 + 2 + 8 + 8 + 8 + 32 + 32 + 32
+        + (4 + 32 * Self::MAX_MODERATORS)
+        + (4 + FeeTier::SIZE * Self::MAX_FEE_TIERS)
+        + 2 + 8
+        + (4 + TreasuryLeg::SIZE * Self::MAX_TREASURY_LEGS)
+        + 8 + 8
+        + 8 + 8 + 8;
+
+    pub fn can_verify(&self, signer: &Pubkey) -> bool {
+        *signer == self.authority
+            || (self.verifier != Pubkey::default() && *signer == self.verifier)
+    }
+
+    pub fn is_moderator(&self, signer: &Pubkey) -> bool {
+        *signer == self.authority || self.moderators.contains(signer)
+    }
+
+    /// Index of the highest fee tier whose `min_balance` the balance meets.
+    pub fn fee_tier(&self, balance: u64) -> Option<u8> {
+        self.fee_tiers
+            .iter()
+            .rposition(|tier| balance >= tier.min_balance)
+            .map(|index| index as u8)
+    }
+
+    pub fn platform_fee(&self, amount: u64) -> u64 {
+        amount
+            .checked_mul(self.fee_percentage as u64)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap()
+    }
+}
+
+#[account]
+pub struct Component {
+    pub creator: Pubkey,
+    pub component_id: String,
+    pub price: u64,  // Price in FLII tokens
+    pub metadata_uri: String,
+    pub is_active: bool,
+    pub total_sales: u64,
+    pub total_rewards_earned: u64,  // Total FLII rewards earned
+    pub created_at: i64,
+    pub is_frozen: bool,  // Set by moderators, blocks purchases
+    pub freeze_reason: u16,
+    pub pending_creator: Option<Pubkey>,  // Proposed new owner awaiting acceptance
+    pub max_supply: Option<u64>,  // Total copies that can ever be sold
+    pub max_per_wallet: Option<u32>,  // Copies a single license holder can own
+    pub sale_start: i64,  // Purchases fail before this time, 0 when unset
+    pub sale_windows: Vec<SaleWindow>,
+    pub dutch_auction: Option<DutchAuction>,  // Overrides price and sale windows when set
+    pub license_mode: LicenseMode,
+    pub resale_royalty_bps: u16,  // Creator's share of license resales
+}
+
+impl Component {
+    pub const MAX_SALE_WINDOWS: usize = 4;
+    pub const MAX_RESALE_ROYALTY_BPS: u16 = 5000; // Max 50%
+    pub const SIZE: usize = 32 + 36 + 8 + 200 + 1 + 8 + 8 + 8 + 1 + 2 + 33 + 9 + 5
+        + 8 + (4 + SaleWindow::SIZE * Self::MAX_SALE_WINDOWS)
+        + (1 + DutchAuction::SIZE)
+        + 1 + 2;
+
+    pub fn is_sold_out(&self) -> bool {
+        matches!(self.max_supply, Some(max_supply) if self.total_sales >= max_supply)
+    }
+
+    /// FLII staking reward (2%) credited to the creator on a sale at `price`.
+    pub fn staking_reward(price: u64) -> u64 {
+        price
+            .checked_mul(200)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap()
+    }
+
+    /// Updates component, marketplace and creator stats for a sale at
+    /// `price` that paid `creator_amount` to the creator. Returns the
+    /// edition sold.
+    pub fn record_sale(
+        &mut self,
+        marketplace: &mut Marketplace,
+        creator_profile: &mut CreatorProfile,
+        price: u64,
+        creator_amount: u64,
+    ) -> u64 {
+        let staking_reward = Self::staking_reward(price);
+        self.total_sales += 1;
+        self.total_rewards_earned += staking_reward;
+        marketplace.total_volume += price;
+        marketplace.total_sales += 1;
+
+        creator_profile.total_sales += 1;
+        creator_profile.lifetime_earnings += creator_amount;
+        creator_profile.lifetime_rewards += staking_reward;
+        self.total_sales
+    }
+
+    /// Price in FLII tokens for a purchase made at `now`.
+    pub fn effective_price(&self, now: i64) -> u64 {
+        if let Some(auction) = &self.dutch_auction {
+            return auction.price_at(now);
+        }
+        self.sale_windows
+            .iter()
+            .find(|w| w.start <= now && now < w.end)
+            .map_or(self.price, |w| w.price)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct TreasuryLeg {
+    pub kind: TreasuryLegKind,
+    pub destination: Pubkey,  // FLII token account, default pubkey for burn legs
+    pub bps: u16,  // Share of the treasury fee
+}
+
+impl TreasuryLeg {
+    pub const SIZE: usize = 1 + 32 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TreasuryLegKind {
+    Operations,
+    StakingRewards,
+    Grants,
+    /// Burned from the fee vault instead of transferred.
+    Burn,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct FeeTier {
+    pub min_balance: u64,  // FLII the buyer must hold, staked FLII counted with its boost
+    pub fee_percentage: u16,  // Basis points charged instead of the flat fee
+}
+
+impl FeeTier {
+    pub const SIZE: usize = 8 + 2;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SaleWindow {
+    pub start: i64,
+    pub end: i64,
+    pub price: u64,  // Sale price in FLII tokens
+}
+
+impl SaleWindow {
+    pub const SIZE: usize = 8 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LicenseMode {
+    /// The `License` account is the entitlement.
+    Receipt,
+    /// A 0-decimal token is minted per license; whoever holds it is entitled.
+    Transferable,
+    /// A non-transferable Token-2022 token is minted per license as proof
+    /// of purchase.
+    Soulbound,
+}
+
+/// Price starts at `start_price` and drops by `decay_amount` every
+/// `decay_interval` seconds after `start_time`, never below `floor_price`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct DutchAuction {
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub start_time: i64,
+    pub decay_interval: i64,
+    pub decay_amount: u64,
+}
+
+impl DutchAuction {
+    pub const SIZE: usize = 8 + 8 + 8 + 8 + 8;
+
+    pub fn price_at(&self, now: i64) -> u64 {
+        if now <= self.start_time {
+            return self.start_price;
+        }
+        let steps = ((now - self.start_time) / self.decay_interval) as u64;
+        let decay = steps.saturating_mul(self.decay_amount);
+        self.start_price.saturating_sub(decay).max(self.floor_price)
+    }
+}
+
+/// Receipt for one purchased copy of a component, addressed by the
+/// component's sale count at the time of purchase.
+#[account]
+pub struct License {
+    pub component: Pubkey,
+    pub owner: Pubkey,  // Wallet entitled to use the component
+    pub purchaser: Pubkey,  // Wallet that paid, differs from owner for gifts
+    pub price: u64,  // Price paid in FLII tokens
+    pub purchased_at: i64,
+    pub gift_message_hash: Option<[u8; 32]>,
+    pub edition: u64,  // 1-based sale number of this copy
+    pub license_mint: Option<Pubkey>,  // Set for tokenized licenses, the token holder is entitled
+}
+
+impl License {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8 + 33 + 8 + 33;
+
+    pub fn record(
+        &mut self,
+        component: Pubkey,
+        owner: Pubkey,
+        purchaser: Pubkey,
+        price: u64,
+        purchased_at: i64,
+        edition: u64,
+    ) {
+        self.component = component;
+        self.owner = owner;
+        self.purchaser = purchaser;
+        self.price = price;
+        self.purchased_at = purchased_at;
+        self.edition = edition;
+    }
+}
+
+/// English auction for one license of a component, one live auction per
+/// component. The highest bid is held in the `[b"auction_escrow", auction]`
+/// token account.
+#[account]
+pub struct Auction {
+    pub component: Pubkey,
+    pub creator: Pubkey,
+    pub reserve_price: u64,  // Minimum winning bid in FLII tokens
+    pub min_increment: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub extension_window: i64,  // Seconds a late bid extends the auction by
+    pub highest_bid: u64,
+    pub highest_bidder: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl Auction {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 33 + 1;
+
+    /// Highest bidder's key as a PDA seed, empty before the first bid.
+    pub fn bidder_seed(&self) -> &[u8] {
+        self.highest_bidder.as_ref().map_or(&[], |bidder| bidder.as_ref())
+    }
+}
+
+/// Fixed-price resale listing of a license. Tokenized licenses are held in
+/// the `[b"license_escrow", license]` token account while listed.
+#[account]
+pub struct LicenseListing {
+    pub seller: Pubkey,
+    pub license: Pubkey,
+    pub price: u64,  // Price in FLII tokens
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl LicenseListing {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+/// Fixed-price listing of an SPL NFT, one per mint. The NFT is held in the
+/// `[b"nft_escrow", nft_mint]` token account until sold or cancelled.
+#[account]
+pub struct NFTListing {
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub price: u64,  // Price in FLII tokens
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl NFTListing {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+/// Standing offer for one license of a component. The offered FLII is held
+/// in the `[b"offer_vault", offer]` token account until the offer is
+/// accepted, cancelled or reclaimed after expiry.
+#[account]
+pub struct Offer {
+    pub buyer: Pubkey,
+    pub component: Pubkey,
+    pub amount: u64,  // Offered FLII tokens
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl Offer {
+    pub const SIZE: usize = 32 + 32 + 8 + 8 + 9 + 1;
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now >= expires_at)
+    }
+}
+
+/// Number of licenses of a component issued to a wallet, used to enforce
+/// `Component::max_per_wallet`.
+#[account]
+pub struct WalletPurchases {
+    pub component: Pubkey,
+    pub wallet: Pubkey,
+    pub count: u32,
+}
+
+impl WalletPurchases {
+    pub const SIZE: usize = 32 + 32 + 4;
+}
+
+/// Leading fields of components listed under the global
+/// `[b"component", component_id]` seeds, read by `migrate_component`.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyComponent {
+    pub creator: Pubkey,
+    pub component_id: String,
+    pub price: u64,
+    pub metadata_uri: String,
+    pub is_active: bool,
+    pub total_sales: u64,
+    pub total_rewards_earned: u64,
+    pub created_at: i64,
+}
+
+#[account]
+pub struct CreatorProfile {
+    pub creator: Pubkey,
+    pub handle: String,
+    pub profile_uri: String,
+    pub payout_wallet: Pubkey,  // Owner of the FLII account that receives sale proceeds
+    pub total_listings: u64,
+    pub total_sales: u64,
+    pub lifetime_earnings: u64,  // Total FLII paid to the creator, rewards included
+    pub lifetime_rewards: u64,  // Total FLII rewards earned
+    pub created_at: i64,
+    pub verified: bool,
+    pub verified_at: i64,
+    pub is_banned: bool,  // Set by moderators, blocks new listings
+    pub ban_reason: u16,
+    pub earnings_vault_enabled: bool,  // Sales pay into earnings_vault instead of payout_wallet
+    pub earnings_vault: Pubkey,  // Default pubkey until the vault is created
+    pub total_claimed: u64,  // Total FLII claimed from the earnings vault
+}
+
+impl CreatorProfile {
+    pub const MAX_HANDLE_LEN: usize = 32;
+    pub const MAX_PROFILE_URI_LEN: usize = 200;
+    pub const SIZE: usize = 32
+        + (4 + Self::MAX_HANDLE_LEN)
+        + (4 + Self::MAX_PROFILE_URI_LEN)
+        + 32 + 8 + 8 + 8 + 8 + 8
+        + 1 + 8
+        + 1 + 2
+        + 1 + 32 + 8;
+
+    pub fn initialize(&mut self, creator: Pubkey, created_at: i64) {
+        self.creator = creator;
+        self.payout_wallet = creator;
+        self.created_at = created_at;
+    }
+
+    /// Whether `account` may receive the creator's sale proceeds: the
+    /// earnings vault when enabled, otherwise any account of the payout wallet.
+    pub fn is_payout_account(&self, account: &Account<TokenAccount>) -> bool {
+        if self.earnings_vault_enabled {
+            account.key() == self.earnings_vault
+        } else {
+            account.owner == self.payout_wallet
+        }
+    }
+}
+
+#[event]
+pub struct ComponentListed {
+    pub component_id: String,
+    pub creator: Pubkey,
+    pub price: u64,  // Price in FLII tokens
+    pub token_mint: Pubkey,
+    pub creator_verified: bool,
+}
+
+#[event]
+pub struct ComponentMigrated {
+    pub component_id: String,
+    pub creator: Pubkey,
+    pub legacy_address: Pubkey,
+    pub address: Pubkey,
+}
+
+#[event]
+pub struct ComponentTransferProposed {
+    pub component_id: String,
+    pub creator: Pubkey,
+    pub new_creator: Pubkey,
+}
+
+#[event]
+pub struct ComponentOwnershipTransferred {
+    pub component_id: String,
+    pub previous_creator: Pubkey,
+    pub new_creator: Pubkey,
+}
+
+#[event]
+pub struct ComponentPurchased {
+    pub component_id: String,
+    pub buyer: Pubkey,
+    pub price: u64,  // Price in FLII tokens
+    pub effective_price: u64,  // Price paid after scheduled sales
+    pub rewards_earned: u64,  // FLII rewards earned
+    pub token_mint: Pubkey,
+    pub recipient: Pubkey,  // License owner, the buyer unless gifted
+    pub license: Pubkey,
+    pub gift_message_hash: Option<[u8; 32]>,
+    pub edition: u64,
+    pub platform_fee: u64,
+    pub fee_tier: Option<u8>,  // Index of the applied holder fee tier
+    pub burned: u64,  // Part of the platform fee burned
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub amount: u64,
+    pub total_fees_collected: u64,
+    pub total_fees_withdrawn: u64,
+}
+
+#[event]
+pub struct TreasuryLegPaid {
+    pub kind: TreasuryLegKind,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FeeBurned {
+    pub amount: u64,
+    pub total_burned: u64,
+}
+
+#[event]
+pub struct SaleScheduled {
+    pub component_id: String,
+    pub start: i64,
+    pub end: i64,
+    pub sale_price: u64,
+}
+
+#[event]
+pub struct DutchAuctionUpdated {
+    pub component_id: String,
+    pub auction: Option<DutchAuction>,
+}
+
+#[event]
+pub struct AuctionCreated {
+    pub component_id: String,
+    pub auction: Pubkey,
+    pub reserve_price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub bid_amount: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct AuctionEnded {
+    pub component_id: String,
+    pub auction: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub winning_bid: u64,
+    pub license: Option<Pubkey>,
+}
+
+#[event]
+pub struct AuctionCancelled {
+    pub auction: Pubkey,
+    pub creator: Pubkey,
+}
+
+#[event]
+pub struct OfferMade {
+    pub offer: Pubkey,
+    pub component_id: String,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub expires_at: Option<i64>,
+}
+
+#[event]
+pub struct OfferCancelled {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub expired: bool,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub offer: Pubkey,
+    pub component_id: String,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub license: Pubkey,
+    pub edition: u64,
+}
+
+#[event]
+pub struct NFTListed {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct NFTListingCancelled {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct NFTSold {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub nft_mint: Pubkey,
+    pub price: u64,
+    pub royalties_paid: u64,  // FLII paid to the NFT's verified creators
+}
+
+#[event]
+pub struct RoyaltyPaid {
+    pub nft_mint: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct LicenseListed {
+    pub listing: Pubkey,
+    pub license: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct LicenseListingCancelled {
+    pub listing: Pubkey,
+    pub license: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct LicenseResold {
+    pub license: Pubkey,
+    pub component_id: String,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub creator_royalty: u64,
+    pub platform_fee: u64,
+}
+
+#[event]
+pub struct SupplyLimitsUpdated {
+    pub component_id: String,
+    pub max_supply: Option<u64>,
+    pub max_per_wallet: Option<u32>,
+}
+
+#[event]
+pub struct CreatorProfileUpdated {
+    pub creator: Pubkey,
+    pub handle: String,
+    pub payout_wallet: Pubkey,
+}
+
+#[event]
+pub struct EarningsVaultUpdated {
+    pub creator: Pubkey,
+    pub earnings_vault: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct EarningsClaimed {
+    pub creator: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct CreatorVerified {
+    pub creator: Pubkey,
+    pub verifier: Pubkey,
+    pub verified_at: i64,
+}
+
+#[event]
+pub struct CreatorUnverified {
+    pub creator: Pubkey,
+    pub verifier: Pubkey,
+}
+
+#[event]
+pub struct ComponentFrozen {
+    pub component_id: String,
+    pub moderator: Pubkey,
+    pub reason_code: u16,
+}
+
+#[event]
+pub struct ComponentUnfrozen {
+    pub component_id: String,
+    pub moderator: Pubkey,
+}
+
+#[event]
+pub struct CreatorBanned {
+    pub creator: Pubkey,
+    pub moderator: Pubkey,
+    pub reason_code: u16,
+}
+
+#[event]
+pub struct CreatorUnbanned {
+    pub creator: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid fee percentage")]
+    InvalidFeePercentage,
+    #[msg("Invalid price")]
+    InvalidPrice,
+    #[msg("Component ID too long")]
+    ComponentIdTooLong,
+    #[msg("Component not active")]
+    ComponentNotActive,
+    #[msg("Unauthorized creator")]
+    UnauthorizedCreator,
+    #[msg("Invalid token mint - must use FLII token")]
+    InvalidTokenMint,
+    #[msg("Insufficient FLII token balance")]
+    InsufficientTokenBalance,
+    #[msg("Creator handle too long")]
+    HandleTooLong,
+    #[msg("Profile URI too long")]
+    ProfileUriTooLong,
+    #[msg("Signer is not the marketplace authority")]
+    UnauthorizedAuthority,
+    #[msg("Signer is not allowed to verify creators")]
+    UnauthorizedVerifier,
+    #[msg("Account is not a marketplace account")]
+    InvalidMarketplaceAccount,
+    #[msg("Signer is not a marketplace moderator")]
+    UnauthorizedModerator,
+    #[msg("Moderator already added")]
+    ModeratorAlreadyAdded,
+    #[msg("Moderator not found")]
+    ModeratorNotFound,
+    #[msg("Too many moderators")]
+    TooManyModerators,
+    #[msg("Component is frozen by moderation")]
+    ComponentFrozen,
+    #[msg("Creator is banned from listing")]
+    CreatorBanned,
+    #[msg("Account is not a component account")]
+    InvalidComponentAccount,
+    #[msg("Invalid transfer recipient")]
+    InvalidTransferRecipient,
+    #[msg("No pending transfer to this creator")]
+    NoPendingTransfer,
+    #[msg("Component is sold out")]
+    SoldOut,
+    #[msg("Wallet purchase limit reached")]
+    WalletLimitReached,
+    #[msg("Invalid supply limit")]
+    InvalidSupplyLimit,
+    #[msg("Sale has not started")]
+    SaleNotStarted,
+    #[msg("Invalid sale window")]
+    InvalidSaleWindow,
+    #[msg("Sale window overlaps an existing one")]
+    SaleWindowOverlap,
+    #[msg("Too many sale windows")]
+    TooManySaleWindows,
+    #[msg("Sale window not found")]
+    SaleWindowNotFound,
+    #[msg("Invalid Dutch auction schedule")]
+    InvalidDutchAuction,
+    #[msg("Price is above the buyer's maximum")]
+    PriceAboveMax,
+    #[msg("Invalid auction schedule")]
+    InvalidAuctionSchedule,
+    #[msg("Auction has not started")]
+    AuctionNotStarted,
+    #[msg("Auction has ended")]
+    AuctionEnded,
+    #[msg("Auction has not ended")]
+    AuctionNotEnded,
+    #[msg("Auction already has bids")]
+    AuctionHasBids,
+    #[msg("Bid too low")]
+    BidTooLow,
+    #[msg("Refund account for the previous bidder is missing")]
+    MissingRefundAccount,
+    #[msg("License account must be passed if and only if the auction sold")]
+    MissingLicenseAccount,
+    #[msg("Offer has expired")]
+    OfferExpired,
+    #[msg("Offer has not expired")]
+    OfferNotExpired,
+    #[msg("Unauthorized buyer")]
+    UnauthorizedBuyer,
+    #[msg("Unauthorized seller")]
+    UnauthorizedSeller,
+    #[msg("Invalid NFT - mint must have 0 decimals and a supply of 1")]
+    InvalidNFT,
+    #[msg("Invalid NFT metadata account")]
+    InvalidNFTMetadata,
+    #[msg("Royalty account missing for a verified creator")]
+    MissingRoyaltyAccount,
+    #[msg("Royalty account does not belong to the creator")]
+    InvalidRoyaltyAccount,
+    #[msg("License token accounts are required for this component")]
+    MissingLicenseTokenAccounts,
+    #[msg("License token accounts are not used by this component")]
+    UnexpectedLicenseTokenAccounts,
+    #[msg("Token does not belong to this license")]
+    InvalidLicenseToken,
+    #[msg("Too many fee tiers")]
+    TooManyFeeTiers,
+    #[msg("Fee tiers must have increasing minimum balances")]
+    InvalidFeeTiers,
+    #[msg("Stake position does not belong to the buyer's FLII stake pool")]
+    InvalidStakePosition,
+    #[msg("Too many treasury legs")]
+    TooManyTreasuryLegs,
+    #[msg("Treasury legs must add up to 10000 basis points")]
+    InvalidTreasurySplit,
+    #[msg("Token account missing for a treasury leg")]
+    MissingTreasuryAccount,
+    #[msg("Token account does not match the treasury leg")]
+    InvalidTreasuryAccount,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Fee vault balance too low")]
+    InsufficientFees,
+    #[msg("Token account cannot receive the creator's proceeds")]
+    InvalidPayoutAccount,
+    #[msg("Earnings vault balance too low")]
+    InsufficientEarnings,
+    #[msg("Wallet purchases account is required for the highest bidder")]
+    MissingWalletPurchasesAccount,
+    #[msg("Offers and auctions cannot issue this kind of license")]
+    LicenseModeNotSupported,
+    #[msg("Soulbound licenses cannot be resold")]
+    LicenseNotTransferable,
+    #[msg("Settle or cancel the component's auction first")]
+    AuctionInProgress,
+    #[msg("Creator is not banned")]
+    CreatorNotBanned,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flii_token::state::MAX_LOCK_DURATION;
+
+    fn dutch_auction() -> DutchAuction {
+        DutchAuction {
+            start_price: 1_000,
+            floor_price: 400,
+            start_time: 100,
+            decay_interval: 10,
+            decay_amount: 50,
+        }
+    }
+
+    #[test]
+    fn dutch_auction_holds_start_price_until_it_starts() {
+        let auction = dutch_auction();
+
+        assert_eq!(auction.price_at(0), 1_000);
+        assert_eq!(auction.price_at(100), 1_000);
+    }
+
+    #[test]
+    fn dutch_auction_decays_at_step_boundaries() {
+        let auction = dutch_auction();
+
+        assert_eq!(auction.price_at(109), 1_000);
+        assert_eq!(auction.price_at(110), 950);
+        assert_eq!(auction.price_at(119), 950);
+        assert_eq!(auction.price_at(120), 900);
+    }
+
+    #[test]
+    fn dutch_auction_stops_at_floor_price() {
+        let auction = dutch_auction();
+
+        assert_eq!(auction.price_at(210), 450);
+        assert_eq!(auction.price_at(220), 400);
+        assert_eq!(auction.price_at(230), 400);
+    }
+
+    #[test]
+    fn dutch_auction_decay_saturates_after_long_elapsed_time() {
+        let auction = DutchAuction {
+            decay_interval: 1,
+            decay_amount: u64::MAX,
+            ..dutch_auction()
+        };
+
+        assert_eq!(auction.price_at(i64::MAX), 400);
+    }
+
+    fn component() -> Component {
+        Component {
+            creator: Pubkey::default(),
+            component_id: String::from("component"),
+            price: 1_000,
+            metadata_uri: String::new(),
+            is_active: true,
+            total_sales: 0,
+            total_rewards_earned: 0,
+            created_at: 0,
+            is_frozen: false,
+            freeze_reason: 0,
+            pending_creator: None,
+            max_supply: None,
+            max_per_wallet: None,
+            sale_start: 0,
+            sale_windows: vec![
+                SaleWindow { start: 100, end: 200, price: 800 },
+                SaleWindow { start: 300, end: 400, price: 600 },
+            ],
+            dutch_auction: None,
+            license_mode: LicenseMode::Receipt,
+            resale_royalty_bps: 0,
+        }
+    }
+
+    #[test]
+    fn effective_price_uses_window_price_inside_a_window() {
+        let component = component();
+
+        assert_eq!(component.effective_price(150), 800);
+        assert_eq!(component.effective_price(350), 600);
+    }
+
+    #[test]
+    fn effective_price_uses_list_price_outside_windows() {
+        let component = component();
+
+        assert_eq!(component.effective_price(50), 1_000);
+        assert_eq!(component.effective_price(250), 1_000);
+        assert_eq!(component.effective_price(500), 1_000);
+    }
+
+    #[test]
+    fn sale_window_includes_start_and_excludes_end() {
+        let component = component();
+
+        assert_eq!(component.effective_price(100), 800);
+        assert_eq!(component.effective_price(199), 800);
+        assert_eq!(component.effective_price(200), 1_000);
+        assert_eq!(component.effective_price(300), 600);
+        assert_eq!(component.effective_price(400), 1_000);
+    }
+
+    #[test]
+    fn dutch_auction_takes_precedence_over_sale_windows() {
+        let component = Component {
+            dutch_auction: Some(dutch_auction()),
+            ..component()
+        };
+
+        assert_eq!(component.effective_price(50), 1_000);
+        assert_eq!(component.effective_price(150), 750);
+        assert_eq!(component.effective_price(350), 400);
+    }
+
+    fn marketplace() -> Marketplace {
+        Marketplace {
+            authority: Pubkey::default(),
+            fee_percentage: 250,
+            total_volume: 0,
+            total_listings: 0,
+            total_sales: 0,
+            flii_token_mint: Pubkey::default(),
+            treasury_wallet: Pubkey::default(),
+            verifier: Pubkey::default(),
+            moderators: vec![],
+            fee_tiers: vec![
+                FeeTier { min_balance: 1_000, fee_percentage: 200 },
+                FeeTier { min_balance: 10_000, fee_percentage: 100 },
+                FeeTier { min_balance: 100_000, fee_percentage: 0 },
+            ],
+            burn_bps: 0,
+            total_burned: 0,
+            treasury_split: vec![],
+            total_fees_collected: 0,
+            total_fees_withdrawn: 0,
+            total_nft_sales: 0,
+            total_resale_volume: 0,
+            total_license_resales: 0,
+        }
+    }
+
+    #[test]
+    fn fee_tier_is_none_below_lowest_tier() {
+        let marketplace = marketplace();
+
+        assert_eq!(marketplace.fee_tier(0), None);
+        assert_eq!(marketplace.fee_tier(999), None);
+    }
+
+    #[test]
+    fn fee_tier_applies_at_exact_threshold() {
+        let marketplace = marketplace();
+
+        assert_eq!(marketplace.fee_tier(1_000), Some(0));
+        assert_eq!(marketplace.fee_tier(9_999), Some(0));
+        assert_eq!(marketplace.fee_tier(10_000), Some(1));
+    }
+
+    #[test]
+    fn fee_tier_caps_at_highest_tier() {
+        let marketplace = marketplace();
+
+        assert_eq!(marketplace.fee_tier(100_000), Some(2));
+        assert_eq!(marketplace.fee_tier(u64::MAX), Some(2));
+    }
+
+    #[test]
+    fn locked_stake_boost_can_reach_a_higher_tier() {
+        let marketplace = marketplace();
+        let position = StakePosition {
+            owner: Pubkey::default(),
+            pool: Pubkey::default(),
+            amount: 4_000,
+            reward_per_token_paid: 0,
+            pending_rewards: 0,
+            bump: 0,
+            lock_start: 0,
+            lock_end: MAX_LOCK_DURATION,
+            boost_bps: StakePosition::boost_for(MAX_LOCK_DURATION),
+            weight: 0,
+        };
+        let wallet_balance = 2_000;
+
+        assert_eq!(marketplace.fee_tier(wallet_balance + position.amount), Some(0));
+        assert_eq!(
+            marketplace.fee_tier(wallet_balance + position.boosted_amount(0)),
+            Some(1)
+        );
+        // The boost lapses with the lock
+        assert_eq!(
+            marketplace.fee_tier(wallet_balance + position.boosted_amount(MAX_LOCK_DURATION)),
+            Some(0)
+        );
+    }
 }
@@ -7,13 +7,16 @@ declare_id!("FLiiRSxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx");
 pub mod revenue_share {
     use super::*;
 
+    /// Creates a pool controlled by the signer. `pool_id` lets one authority
+    /// run several pools side by side.
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
+        pool_id: u64,
         creator_share: u16,
         platform_share: u16,
     ) -> Result<()> {
         require!(
-            creator_share + platform_share == 10000,
+            creator_share as u32 + platform_share as u32 == 10000,
             ErrorCode::InvalidShares
         );
 
@@ -22,6 +25,44 @@ pub mod revenue_share {
         pool.creator_share = creator_share;
         pool.platform_share = platform_share;
         pool.total_distributed = 0;
+        pool.pool_id = pool_id;
+        pool.bump = ctx.bumps.pool;
+
+        Ok(())
+    }
+
+    pub fn update_pool(
+        ctx: Context<UpdatePool>,
+        creator_share: u16,
+        platform_share: u16,
+    ) -> Result<()> {
+        require!(
+            creator_share as u32 + platform_share as u32 == 10000,
+            ErrorCode::InvalidShares
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        pool.creator_share = creator_share;
+        pool.platform_share = platform_share;
+
+        emit!(PoolUpdated {
+            pool: pool.key(),
+            creator_share,
+            platform_share,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+
+        emit!(PoolClosed {
+            pool: pool.key(),
+            authority: pool.authority,
+            pool_id: pool.pool_id,
+            total_distributed: pool.total_distributed,
+        });
 
         Ok(())
     }
@@ -61,6 +102,7 @@ pub mod revenue_share {
         pool.total_distributed += amount;
 
         emit!(RevenueDistributed {
+            pool: pool.key(),
             amount,
             creator_amount,
             platform_amount,
@@ -71,12 +113,13 @@ pub mod revenue_share {
 }
 
 #[derive(Accounts)]
+#[instruction(pool_id: u64)]
 pub struct InitializePool<'info> {
     #[account(
         init,
         payer = authority,
         space = 8 + RevenuePool::SIZE,
-        seeds = [b"revenue_pool"],
+        seeds = [b"revenue_pool", authority.key().as_ref(), &pool_id.to_le_bytes()],
         bump
     )]
     pub pool: Account<'info, RevenuePool>,
@@ -86,8 +129,38 @@ pub struct InitializePool<'info> {
 }
 
 #[derive(Accounts)]
-pub struct DistributeRevenue<'info> {
+pub struct UpdatePool<'info> {
+    #[account(
+        mut,
+        seeds = [b"revenue_pool", pool.authority.as_ref(), &pool.pool_id.to_le_bytes()],
+        bump = pool.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub pool: Account<'info, RevenuePool>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(
+        mut,
+        seeds = [b"revenue_pool", pool.authority.as_ref(), &pool.pool_id.to_le_bytes()],
+        bump = pool.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAuthority,
+        close = authority
+    )]
+    pub pool: Account<'info, RevenuePool>,
     #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"revenue_pool", pool.authority.as_ref(), &pool.pool_id.to_le_bytes()],
+        bump = pool.bump
+    )]
     pub pool: Account<'info, RevenuePool>,
     #[account(mut)]
     pub source_account: Account<'info, TokenAccount>,
@@ -105,21 +178,41 @@ pub struct RevenuePool {
     pub creator_share: u16,
     pub platform_share: u16,
     pub total_distributed: u64,
+    pub pool_id: u64,
+    pub bump: u8,
 }
 
 impl RevenuePool {
-    pub const SIZE: usize = 32 + 2 + 2 + 8;
+    pub const SIZE: usize = 32 + 2 + 2 + 8 + 8 + 1;
 }
 
 #[event]
 pub struct RevenueDistributed {
+    pub pool: Pubkey,
     pub amount: u64,
     pub creator_amount: u64,
     pub platform_amount: u64,
 }
 
+#[event]
+pub struct PoolUpdated {
+    pub pool: Pubkey,
+    pub creator_share: u16,
+    pub platform_share: u16,
+}
+
+#[event]
+pub struct PoolClosed {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub pool_id: u64,
+    pub total_distributed: u64,
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Invalid share percentages")]
     InvalidShares,
+    #[msg("Unauthorized pool authority")]
+    UnauthorizedAuthority,
 }
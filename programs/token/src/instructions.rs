@@ -1 +1,557 @@
 // Token program instructions
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::state::*;
+use crate::{
+    ErrorCode, PositionRefreshed, RewardsClaimed, RewardsDistributed, StakeLocked, Staked,
+    Unstaked, VestingClaimed, VestingCreated, VestingRevoked,
+};
+
+/// Creates the stake pool for a mint. Permissionless: there is one pool per
+/// mint and nothing about it can be administered afterwards.
+pub fn initialize_stake_pool(ctx: Context<InitializeStakePool>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.flii_mint = ctx.accounts.flii_mint.key();
+    pool.stake_vault = ctx.accounts.stake_vault.key();
+    pool.reward_vault = ctx.accounts.reward_vault.key();
+    pool.total_staked = 0;
+    pool.total_weight = 0;
+    pool.reward_per_token = 0;
+    pool.queued_rewards = 0;
+    pool.total_rewards_distributed = 0;
+    pool.total_rewards_claimed = 0;
+    pool.bump = ctx.bumps.pool;
+
+    Ok(())
+}
+
+pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.owner_token_account.to_account_info(),
+        to: ctx.accounts.stake_vault.to_account_info(),
+        authority: ctx.accounts.owner.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, amount)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+    if position.owner == Pubkey::default() {
+        position.owner = ctx.accounts.owner.key();
+        position.pool = pool.key();
+        position.reward_per_token_paid = pool.reward_per_token;
+        position.boost_bps = BASE_BOOST_BPS;
+        position.bump = ctx.bumps.position;
+    }
+    // Added stake joins any active lock
+    position.settle(pool.reward_per_token);
+    position.amount = position.amount.checked_add(amount).unwrap();
+    position.refresh_weight(pool, now);
+
+    pool.total_staked = pool.total_staked.checked_add(amount).unwrap();
+    // Pays out anything queued while the pool was empty
+    pool.distribute(0);
+
+    emit!(Staked {
+        owner: position.owner,
+        amount,
+        total_staked: pool.total_staked,
+        weight: position.weight,
+    });
+
+    Ok(())
+}
+
+pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+    require!(!position.is_locked(now), ErrorCode::StakeLocked);
+    require!(position.amount >= amount, ErrorCode::InsufficientStake);
+
+    position.settle(pool.reward_per_token);
+    position.amount -= amount;
+    position.refresh_weight(pool, now);
+    pool.total_staked -= amount;
+
+    let seeds = &[b"stake_pool".as_ref(), pool.flii_mint.as_ref(), &[pool.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.stake_vault.to_account_info(),
+        to: ctx.accounts.owner_token_account.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)?;
+
+    emit!(Unstaked {
+        owner: position.owner,
+        amount,
+        total_staked: pool.total_staked,
+    });
+
+    Ok(())
+}
+
+/// Locks the whole position for `lock_duration` seconds from now in exchange
+/// for a reward boost. A lock can only be extended, never shortened.
+pub fn lock_stake(ctx: Context<UpdatePosition>, lock_duration: i64) -> Result<()> {
+    require!(
+        lock_duration > 0 && lock_duration <= MAX_LOCK_DURATION,
+        ErrorCode::InvalidLockDuration
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+    require!(position.amount > 0, ErrorCode::InsufficientStake);
+
+    let lock_end = now.checked_add(lock_duration).unwrap();
+    require!(lock_end >= position.lock_end, ErrorCode::LockCannotShorten);
+
+    position.settle(pool.reward_per_token);
+    position.lock_start = now;
+    position.lock_end = lock_end;
+    position.boost_bps = StakePosition::boost_for(lock_duration);
+    position.refresh_weight(pool, now);
+
+    emit!(StakeLocked {
+        owner: position.owner,
+        amount: position.amount,
+        lock_end,
+        boost_bps: position.boost_bps,
+        weight: position.weight,
+        voting_power: position.voting_power(now),
+    });
+
+    Ok(())
+}
+
+/// Drops the boost of a position whose lock has ended. Permissionless so
+/// expired locks stop diluting other stakers without the owner acting.
+pub fn refresh_position(ctx: Context<RefreshPosition>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+
+    position.settle(pool.reward_per_token);
+    position.refresh_weight(pool, now);
+
+    emit!(PositionRefreshed {
+        owner: position.owner,
+        weight: position.weight,
+        voting_power: position.voting_power(now),
+    });
+
+    Ok(())
+}
+
+pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let position = &mut ctx.accounts.position;
+    position.settle(pool.reward_per_token);
+
+    let amount = position.pending_rewards;
+    require!(amount > 0, ErrorCode::NothingToClaim);
+    position.pending_rewards = 0;
+    pool.total_rewards_claimed = pool.total_rewards_claimed.checked_add(amount).unwrap();
+
+    let seeds = &[b"stake_pool".as_ref(), pool.flii_mint.as_ref(), &[pool.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.reward_vault.to_account_info(),
+        to: ctx.accounts.owner_token_account.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)?;
+
+    emit!(RewardsClaimed {
+        owner: position.owner,
+        amount,
+    });
+
+    Ok(())
+}
+
+pub fn distribute_rewards(ctx: Context<DistributeRewards>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let amount = ctx
+        .accounts
+        .reward_vault
+        .amount
+        .checked_sub(pool.accounted_rewards())
+        .unwrap();
+    require!(amount > 0, ErrorCode::NothingToDistribute);
+
+    pool.distribute(amount);
+
+    emit!(RewardsDistributed {
+        amount,
+        reward_per_token: pool.reward_per_token,
+        total_weight: pool.total_weight,
+    });
+
+    Ok(())
+}
+
+/// Locks `total_amount` from the grantor in a vault that releases to the
+/// beneficiary on the given schedule.
+#[allow(clippy::too_many_arguments)]
+pub fn create_vesting(
+    ctx: Context<CreateVesting>,
+    schedule_id: u64,
+    total_amount: u64,
+    start_time: i64,
+    cliff_time: i64,
+    end_time: i64,
+    revocable: bool,
+) -> Result<()> {
+    require!(total_amount > 0, ErrorCode::InvalidAmount);
+    require!(
+        start_time <= cliff_time && cliff_time <= end_time && start_time < end_time,
+        ErrorCode::InvalidVestingSchedule
+    );
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.grantor_token_account.to_account_info(),
+        to: ctx.accounts.vault.to_account_info(),
+        authority: ctx.accounts.grantor.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, total_amount)?;
+
+    let vesting = &mut ctx.accounts.vesting;
+    vesting.grantor = ctx.accounts.grantor.key();
+    vesting.beneficiary = ctx.accounts.beneficiary.key();
+    vesting.mint = ctx.accounts.mint.key();
+    vesting.vault = ctx.accounts.vault.key();
+    vesting.schedule_id = schedule_id;
+    vesting.total_amount = total_amount;
+    vesting.claimed_amount = 0;
+    vesting.start_time = start_time;
+    vesting.cliff_time = cliff_time;
+    vesting.end_time = end_time;
+    vesting.revocable = revocable;
+    vesting.revoked = false;
+    vesting.bump = ctx.bumps.vesting;
+
+    emit!(VestingCreated {
+        vesting: vesting.key(),
+        grantor: vesting.grantor,
+        beneficiary: vesting.beneficiary,
+        mint: vesting.mint,
+        total_amount,
+        start_time,
+        cliff_time,
+        end_time,
+        revocable,
+    });
+
+    Ok(())
+}
+
+pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let vesting = &mut ctx.accounts.vesting;
+    let amount = vesting
+        .vested_amount(now)
+        .checked_sub(vesting.claimed_amount)
+        .unwrap();
+    require!(amount > 0, ErrorCode::NothingToClaim);
+    vesting.claimed_amount = vesting.claimed_amount.checked_add(amount).unwrap();
+
+    let schedule_id = vesting.schedule_id.to_le_bytes();
+    let seeds = &[
+        b"vesting".as_ref(),
+        vesting.grantor.as_ref(),
+        vesting.beneficiary.as_ref(),
+        schedule_id.as_ref(),
+        &[vesting.bump],
+    ];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.vault.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: vesting.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)?;
+
+    emit!(VestingClaimed {
+        vesting: vesting.key(),
+        beneficiary: vesting.beneficiary,
+        amount,
+        claimed_amount: vesting.claimed_amount,
+    });
+
+    Ok(())
+}
+
+/// Stops a revocable schedule. Whatever has vested so far stays claimable
+/// by the beneficiary; the unvested remainder returns to the grantor.
+pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let vesting = &mut ctx.accounts.vesting;
+    require!(vesting.revocable, ErrorCode::VestingNotRevocable);
+    require!(!vesting.revoked, ErrorCode::AlreadyRevoked);
+
+    let unvested = vesting.revoke(now);
+    let vested = vesting.total_amount;
+
+    if unvested > 0 {
+        let schedule_id = vesting.schedule_id.to_le_bytes();
+        let seeds = &[
+            b"vesting".as_ref(),
+            vesting.grantor.as_ref(),
+            vesting.beneficiary.as_ref(),
+            schedule_id.as_ref(),
+            &[vesting.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.grantor_token_account.to_account_info(),
+            authority: vesting.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        token::transfer(cpi_ctx, unvested)?;
+    }
+
+    emit!(VestingRevoked {
+        vesting: vesting.key(),
+        grantor: vesting.grantor,
+        beneficiary: vesting.beneficiary,
+        vested_amount: vested,
+        returned_amount: unvested,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + StakePool::SIZE,
+        seeds = [b"stake_pool", flii_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, StakePool>,
+    pub flii_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"stake_vault", pool.key().as_ref()],
+        bump,
+        token::mint = flii_mint,
+        token::authority = pool
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"reward_vault", pool.key().as_ref()],
+        bump,
+        token::mint = flii_mint,
+        token::authority = pool
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut, has_one = stake_vault)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + StakePosition::SIZE,
+        seeds = [b"stake_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, StakePosition>,
+    #[account(mut)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == pool.flii_mint @ ErrorCode::InvalidTokenMint,
+        constraint = owner_token_account.owner == owner.key()
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(mut, has_one = stake_vault)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        seeds = [b"stake_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, StakePosition>,
+    #[account(mut)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == pool.flii_mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePosition<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        seeds = [b"stake_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, StakePosition>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshPosition<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, StakePool>,
+    #[account(mut, has_one = pool)]
+    pub position: Account<'info, StakePosition>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut, has_one = reward_vault)]
+    pub pool: Account<'info, StakePool>,
+    #[account(
+        mut,
+        seeds = [b"stake_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, StakePosition>,
+    #[account(mut)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == pool.flii_mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeRewards<'info> {
+    #[account(mut, has_one = reward_vault)]
+    pub pool: Account<'info, StakePool>,
+    pub reward_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+#[instruction(schedule_id: u64)]
+pub struct CreateVesting<'info> {
+    #[account(
+        init,
+        payer = grantor,
+        space = 8 + VestingSchedule::SIZE,
+        seeds = [
+            b"vesting",
+            grantor.key().as_ref(),
+            beneficiary.key().as_ref(),
+            &schedule_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub vesting: Account<'info, VestingSchedule>,
+    #[account(
+        init,
+        payer = grantor,
+        seeds = [b"vesting_vault", vesting.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vesting
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = grantor_token_account.mint == mint.key() @ ErrorCode::InvalidTokenMint
+    )]
+    pub grantor_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Only recorded as the beneficiary of the schedule
+    pub beneficiary: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(mut, has_one = beneficiary, has_one = vault)]
+    pub vesting: Account<'info, VestingSchedule>,
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = destination.mint == vesting.mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    pub beneficiary: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    #[account(mut, has_one = grantor, has_one = vault)]
+    pub vesting: Account<'info, VestingSchedule>,
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = grantor_token_account.mint == vesting.mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub grantor_token_account: Account<'info, TokenAccount>,
+    pub grantor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
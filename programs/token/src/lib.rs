@@ -3,16 +3,176 @@ pub mod state;
 
 use anchor_lang::prelude::*;
 
-declare_id!("TokenProgramID11111111111111111111111111111");
+use instructions::*;
 
+declare_id!("FLiiTKxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx");
+
+/// FLII staking and vesting. Stakers deposit FLII into the pool's vault and
+/// earn a share of the platform fees paid into the reward vault, weighted by
+/// the boost of any time lock on their stake. Vesting schedules release
+/// grants from a PDA vault after a cliff.
 #[program]
 pub mod token {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        Ok(())
+    pub fn initialize_stake_pool(ctx: Context<InitializeStakePool>) -> Result<()> {
+        instructions::initialize_stake_pool(ctx)
+    }
+
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        instructions::stake(ctx, amount)
+    }
+
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        instructions::unstake(ctx, amount)
+    }
+
+    pub fn lock_stake(ctx: Context<UpdatePosition>, lock_duration: i64) -> Result<()> {
+        instructions::lock_stake(ctx, lock_duration)
+    }
+
+    pub fn refresh_position(ctx: Context<RefreshPosition>) -> Result<()> {
+        instructions::refresh_position(ctx)
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        instructions::claim_rewards(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        schedule_id: u64,
+        total_amount: u64,
+        start_time: i64,
+        cliff_time: i64,
+        end_time: i64,
+        revocable: bool,
+    ) -> Result<()> {
+        instructions::create_vesting(
+            ctx,
+            schedule_id,
+            total_amount,
+            start_time,
+            cliff_time,
+            end_time,
+            revocable,
+        )
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        instructions::claim_vested(ctx)
+    }
+
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+        instructions::revoke_vesting(ctx)
+    }
+
+    /// Adds FLII transferred into the reward vault since the last call to
+    /// the reward index. Permissionless, so the marketplace treasury can
+    /// route fees with a plain token transfer.
+    pub fn distribute_rewards(ctx: Context<DistributeRewards>) -> Result<()> {
+        instructions::distribute_rewards(ctx)
     }
 }
 
-#[derive(Accounts)]
-pub struct Initialize {}
+#[event]
+pub struct Staked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+    pub weight: u64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct StakeLocked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub lock_end: i64,
+    pub boost_bps: u16,
+    pub weight: u64,
+    pub voting_power: u64,
+}
+
+#[event]
+pub struct PositionRefreshed {
+    pub owner: Pubkey,
+    pub weight: u64,
+    pub voting_power: u64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RewardsDistributed {
+    pub amount: u64,
+    pub reward_per_token: u128,
+    pub total_weight: u64,
+}
+
+#[event]
+pub struct VestingCreated {
+    pub vesting: Pubkey,
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    pub revocable: bool,
+}
+
+#[event]
+pub struct VestingClaimed {
+    pub vesting: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+}
+
+#[event]
+pub struct VestingRevoked {
+    pub vesting: Pubkey,
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub vested_amount: u64,
+    pub returned_amount: u64,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Invalid token mint")]
+    InvalidTokenMint,
+    #[msg("Not enough staked")]
+    InsufficientStake,
+    #[msg("No rewards to claim")]
+    NothingToClaim,
+    #[msg("No new rewards to distribute")]
+    NothingToDistribute,
+    #[msg("Lock duration must be positive and at most the maximum lock")]
+    InvalidLockDuration,
+    #[msg("A lock can only be extended")]
+    LockCannotShorten,
+    #[msg("Stake is still locked")]
+    StakeLocked,
+    #[msg("Vesting must start no later than the cliff and end after it starts")]
+    InvalidVestingSchedule,
+    #[msg("Vesting schedule is not revocable")]
+    VestingNotRevocable,
+    #[msg("Vesting schedule already revoked")]
+    AlreadyRevoked,
+}
@@ -1 +1,412 @@
 // Token program state
+use anchor_lang::prelude::*;
+
+/// Scale applied to `StakePool::reward_per_token` so small deposits spread
+/// over a large stake still move the index.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Longest lock a stake position can commit to (4 years).
+pub const MAX_LOCK_DURATION: i64 = 4 * 365 * 24 * 60 * 60;
+
+/// Unlocked stake earns rewards at 1x.
+pub const BASE_BOOST_BPS: u16 = 10000;
+
+/// Extra boost for a maximum-length lock, scaled linearly for shorter ones.
+pub const MAX_EXTRA_BOOST_BPS: u16 = 10000;
+
+#[account]
+pub struct StakePool {
+    pub flii_mint: Pubkey,
+    pub stake_vault: Pubkey,  // Holds staked FLII
+    pub reward_vault: Pubkey,  // Holds fee rewards waiting to be claimed
+    pub total_staked: u64,
+    pub total_weight: u64,  // Sum of boosted position weights
+    pub reward_per_token: u128,  // Cumulative rewards per unit of weight, scaled by REWARD_PRECISION
+    pub queued_rewards: u64,  // Rewards received while nothing was staked
+    pub total_rewards_distributed: u64,
+    pub total_rewards_claimed: u64,
+    pub bump: u8,
+}
+
+impl StakePool {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 8 + 16 + 8 + 8 + 8 + 1;
+
+    /// Rewards sitting in the reward vault that have already been
+    /// distributed, either into the index or the queue.
+    pub fn accounted_rewards(&self) -> u64 {
+        self.total_rewards_distributed
+            .checked_sub(self.total_rewards_claimed)
+            .unwrap()
+    }
+
+    /// Adds `amount` of new rewards to the index. With nothing staked the
+    /// rewards are queued and paid out with the next distribution.
+    pub fn distribute(&mut self, amount: u64) {
+        self.total_rewards_distributed = self.total_rewards_distributed.checked_add(amount).unwrap();
+        self.queued_rewards = self.queued_rewards.checked_add(amount).unwrap();
+        if self.total_weight == 0 || self.queued_rewards == 0 {
+            return;
+        }
+
+        let increment = (self.queued_rewards as u128)
+            .checked_mul(REWARD_PRECISION)
+            .unwrap()
+            .checked_div(self.total_weight as u128)
+            .unwrap();
+        self.reward_per_token = self.reward_per_token.checked_add(increment).unwrap();
+        self.queued_rewards = 0;
+    }
+}
+
+#[account]
+pub struct StakePosition {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub reward_per_token_paid: u128,  // Pool index when rewards were last settled
+    pub pending_rewards: u64,  // Settled but unclaimed rewards
+    pub bump: u8,
+    pub lock_start: i64,
+    pub lock_end: i64,  // Stake cannot be withdrawn before this time
+    pub boost_bps: u16,  // Reward multiplier earned by the current lock
+    pub weight: u64,  // Boosted amount counted in the pool's reward index
+}
+
+impl StakePosition {
+    pub const SIZE: usize = 32 + 32 + 8 + 16 + 8 + 1 + 8 + 8 + 2 + 8;
+
+    /// Boost earned by committing to a lock of `lock_duration` seconds.
+    pub fn boost_for(lock_duration: i64) -> u16 {
+        let extra = (MAX_EXTRA_BOOST_BPS as i64)
+            .checked_mul(lock_duration.clamp(0, MAX_LOCK_DURATION))
+            .unwrap()
+            .checked_div(MAX_LOCK_DURATION)
+            .unwrap();
+        BASE_BOOST_BPS + extra as u16
+    }
+
+    pub fn is_locked(&self, now: i64) -> bool {
+        now < self.lock_end
+    }
+
+    /// Stake multiplied by the lock boost, which lapses once the lock ends.
+    pub fn boosted_amount(&self, now: i64) -> u64 {
+        let boost_bps = if self.is_locked(now) {
+            self.boost_bps
+        } else {
+            BASE_BOOST_BPS
+        };
+        ((self.amount as u128) * (boost_bps as u128) / (BASE_BOOST_BPS as u128)) as u64
+    }
+
+    /// Voting power of the locked stake, decaying linearly to zero at
+    /// `lock_end`. A maximum-length lock starts at 1 vote per token.
+    pub fn voting_power(&self, now: i64) -> u64 {
+        let remaining = self.lock_end.saturating_sub(now).clamp(0, MAX_LOCK_DURATION);
+        ((self.amount as u128) * (remaining as u128) / (MAX_LOCK_DURATION as u128)) as u64
+    }
+
+    /// Recomputes the position's reward weight and updates the pool total.
+    /// Settle before calling so past rewards use the old weight.
+    pub fn refresh_weight(&mut self, pool: &mut StakePool, now: i64) {
+        let weight = self.boosted_amount(now);
+        pool.total_weight = pool
+            .total_weight
+            .checked_sub(self.weight)
+            .unwrap()
+            .checked_add(weight)
+            .unwrap();
+        self.weight = weight;
+    }
+
+    /// Moves rewards earned since the last settlement into `pending_rewards`.
+    pub fn settle(&mut self, reward_per_token: u128) {
+        let earned = (self.weight as u128)
+            .checked_mul(reward_per_token.checked_sub(self.reward_per_token_paid).unwrap())
+            .unwrap()
+            .checked_div(REWARD_PRECISION)
+            .unwrap();
+        self.pending_rewards = self.pending_rewards.checked_add(earned as u64).unwrap();
+        self.reward_per_token_paid = reward_per_token;
+    }
+}
+
+#[account]
+pub struct VestingSchedule {
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,  // PDA token account holding unclaimed tokens
+    pub schedule_id: u64,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,  // Nothing vests before this time
+    pub end_time: i64,
+    pub revocable: bool,
+    pub revoked: bool,
+    pub bump: u8,
+}
+
+impl VestingSchedule {
+    pub const SIZE: usize = 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 1;
+
+    /// Amount vested at `now`: zero before the cliff, then released
+    /// linearly from `start_time` until everything has vested at `end_time`.
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        if now < self.cliff_time {
+            return 0;
+        }
+        if now >= self.end_time {
+            return self.total_amount;
+        }
+
+        let elapsed = now.checked_sub(self.start_time).unwrap() as u128;
+        let duration = self.end_time.checked_sub(self.start_time).unwrap() as u128;
+        ((self.total_amount as u128) * elapsed / duration) as u64
+    }
+
+    /// Freezes the schedule at what has vested by `now` and returns the
+    /// unvested remainder owed back to the grantor.
+    pub fn revoke(&mut self, now: i64) -> u64 {
+        let vested = self.vested_amount(now);
+        let unvested = self.total_amount.checked_sub(vested).unwrap();
+
+        self.total_amount = vested;
+        self.end_time = now.max(self.start_time + 1);
+        self.cliff_time = self.cliff_time.min(self.end_time);
+        self.revoked = true;
+        unvested
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> StakePool {
+        StakePool {
+            flii_mint: Pubkey::default(),
+            stake_vault: Pubkey::default(),
+            reward_vault: Pubkey::default(),
+            total_staked: 0,
+            total_weight: 0,
+            reward_per_token: 0,
+            queued_rewards: 0,
+            total_rewards_distributed: 0,
+            total_rewards_claimed: 0,
+            bump: 0,
+        }
+    }
+
+    fn position(pool: &mut StakePool, amount: u64, now: i64) -> StakePosition {
+        let mut position = StakePosition {
+            owner: Pubkey::new_unique(),
+            pool: Pubkey::default(),
+            amount,
+            reward_per_token_paid: pool.reward_per_token,
+            pending_rewards: 0,
+            bump: 0,
+            lock_start: 0,
+            lock_end: 0,
+            boost_bps: BASE_BOOST_BPS,
+            weight: 0,
+        };
+        position.refresh_weight(pool, now);
+        pool.total_staked += amount;
+        position
+    }
+
+    #[test]
+    fn distribute_splits_rewards_pro_rata() {
+        let mut pool = pool();
+        let mut alice = position(&mut pool, 100, 0);
+        let mut bob = position(&mut pool, 300, 0);
+
+        pool.distribute(1_000);
+        alice.settle(pool.reward_per_token);
+        bob.settle(pool.reward_per_token);
+
+        assert_eq!(alice.pending_rewards, 250);
+        assert_eq!(bob.pending_rewards, 750);
+        assert_eq!(pool.queued_rewards, 0);
+        assert_eq!(pool.accounted_rewards(), 1_000);
+    }
+
+    #[test]
+    fn late_staker_does_not_earn_earlier_rewards() {
+        let mut pool = pool();
+        let mut alice = position(&mut pool, 100, 0);
+        pool.distribute(500);
+
+        alice.settle(pool.reward_per_token);
+        let mut bob = position(&mut pool, 100, 0);
+        pool.distribute(200);
+        alice.settle(pool.reward_per_token);
+        bob.settle(pool.reward_per_token);
+
+        assert_eq!(alice.pending_rewards, 600);
+        assert_eq!(bob.pending_rewards, 100);
+    }
+
+    #[test]
+    fn distribute_queues_rewards_while_pool_is_empty() {
+        let mut pool = pool();
+        pool.distribute(500);
+
+        assert_eq!(pool.reward_per_token, 0);
+        assert_eq!(pool.queued_rewards, 500);
+        assert_eq!(pool.accounted_rewards(), 500);
+
+        let mut alice = position(&mut pool, 100, 0);
+        pool.distribute(0);
+        alice.settle(pool.reward_per_token);
+
+        assert_eq!(pool.queued_rewards, 0);
+        assert_eq!(alice.pending_rewards, 500);
+        assert_eq!(pool.accounted_rewards(), 500);
+    }
+
+    #[test]
+    fn distribute_nothing_to_empty_pool_is_a_no_op() {
+        let mut pool = pool();
+        pool.distribute(0);
+
+        assert_eq!(pool.reward_per_token, 0);
+        assert_eq!(pool.queued_rewards, 0);
+        assert_eq!(pool.total_rewards_distributed, 0);
+    }
+
+    #[test]
+    fn settle_only_pays_rewards_since_last_settlement() {
+        let mut pool = pool();
+        let mut alice = position(&mut pool, 100, 0);
+
+        pool.distribute(100);
+        alice.settle(pool.reward_per_token);
+        alice.settle(pool.reward_per_token);
+        assert_eq!(alice.pending_rewards, 100);
+
+        pool.distribute(200);
+        alice.settle(pool.reward_per_token);
+        assert_eq!(alice.pending_rewards, 300);
+        assert_eq!(alice.reward_per_token_paid, pool.reward_per_token);
+    }
+
+    #[test]
+    fn refresh_weight_applies_boost_until_lock_ends() {
+        let mut pool = pool();
+        let mut alice = position(&mut pool, 100, 0);
+        let bob = position(&mut pool, 100, 0);
+
+        alice.lock_start = 0;
+        alice.lock_end = MAX_LOCK_DURATION;
+        alice.boost_bps = StakePosition::boost_for(MAX_LOCK_DURATION);
+        alice.refresh_weight(&mut pool, 0);
+        assert_eq!(alice.weight, 200);
+        assert_eq!(pool.total_weight, 300);
+
+        pool.distribute(300);
+        alice.settle(pool.reward_per_token);
+        assert_eq!(alice.pending_rewards, 200);
+
+        alice.refresh_weight(&mut pool, MAX_LOCK_DURATION);
+        assert_eq!(alice.weight, 100);
+        assert_eq!(pool.total_weight, alice.weight + bob.weight);
+    }
+
+    fn vesting(total_amount: u64, start_time: i64, cliff_time: i64, end_time: i64) -> VestingSchedule {
+        VestingSchedule {
+            grantor: Pubkey::default(),
+            beneficiary: Pubkey::default(),
+            mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            schedule_id: 0,
+            total_amount,
+            claimed_amount: 0,
+            start_time,
+            cliff_time,
+            end_time,
+            revocable: true,
+            revoked: false,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn nothing_vests_before_the_cliff() {
+        let schedule = vesting(1_000, 100, 200, 1_100);
+
+        assert_eq!(schedule.vested_amount(0), 0);
+        assert_eq!(schedule.vested_amount(100), 0);
+        assert_eq!(schedule.vested_amount(199), 0);
+    }
+
+    #[test]
+    fn cliff_releases_everything_accrued_since_start() {
+        let schedule = vesting(1_000, 100, 200, 1_100);
+
+        assert_eq!(schedule.vested_amount(200), 100);
+    }
+
+    #[test]
+    fn vesting_is_linear_between_cliff_and_end() {
+        let schedule = vesting(1_000, 100, 200, 1_100);
+
+        assert_eq!(schedule.vested_amount(350), 250);
+        assert_eq!(schedule.vested_amount(600), 500);
+        assert_eq!(schedule.vested_amount(1_099), 999);
+    }
+
+    #[test]
+    fn everything_vests_at_and_after_the_end() {
+        let schedule = vesting(1_000, 100, 200, 1_100);
+
+        assert_eq!(schedule.vested_amount(1_100), 1_000);
+        assert_eq!(schedule.vested_amount(5_000), 1_000);
+    }
+
+    #[test]
+    fn revoke_before_start_returns_everything() {
+        let mut schedule = vesting(1_000, 100, 200, 1_100);
+
+        assert_eq!(schedule.revoke(50), 1_000);
+        assert!(schedule.revoked);
+        assert_eq!(schedule.total_amount, 0);
+        assert_eq!(schedule.vested_amount(50), 0);
+        assert_eq!(schedule.vested_amount(5_000), 0);
+    }
+
+    #[test]
+    fn revoke_mid_schedule_keeps_what_has_vested() {
+        let mut schedule = vesting(1_000, 100, 200, 1_100);
+
+        assert_eq!(schedule.revoke(600), 500);
+        assert_eq!(schedule.total_amount, 500);
+        assert_eq!(schedule.end_time, 600);
+        assert_eq!(schedule.vested_amount(600), 500);
+        assert_eq!(schedule.vested_amount(5_000), 500);
+    }
+
+    #[test]
+    fn revoke_before_cliff_pulls_the_cliff_in() {
+        let mut schedule = vesting(1_000, 100, 200, 1_100);
+
+        assert_eq!(schedule.revoke(150), 1_000);
+        assert_eq!(schedule.cliff_time, 150);
+        assert_eq!(schedule.vested_amount(5_000), 0);
+    }
+
+    #[test]
+    fn claims_after_revoke_stop_at_the_vested_amount() {
+        let mut schedule = vesting(1_000, 100, 200, 1_100);
+        schedule.claimed_amount = schedule.vested_amount(350);
+
+        schedule.revoke(600);
+        let claimable = schedule.vested_amount(5_000) - schedule.claimed_amount;
+
+        assert_eq!(claimable, 250);
+        schedule.claimed_amount += claimable;
+        assert_eq!(schedule.vested_amount(10_000), schedule.claimed_amount);
+    }
+}
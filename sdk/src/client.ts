@@ -1,4 +1,4 @@
-import { Program, AnchorProvider, Idl, setProvider } from '@coral-xyz/anchor';
+import { Program, AnchorProvider, BN, Idl, setProvider } from '@coral-xyz/anchor';
 import { Connection, PublicKey, Keypair } from '@solana/web3.js';
 import { Marketplace } from './types';
 
@@ -43,7 +43,16 @@ export class MarketplaceClient {
     metadataUri: string
   ) {
     const [componentPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('component'), Buffer.from(componentId)],
+      [
+        Buffer.from('component'),
+        this.provider.wallet.publicKey.toBuffer(),
+        Buffer.from(componentId)
+      ],
+      this.program.programId
+    );
+
+    const [creatorProfilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('creator_profile'), this.provider.wallet.publicKey.toBuffer()],
       this.program.programId
     );
 
@@ -56,23 +65,93 @@ export class MarketplaceClient {
       .listComponent(componentId, price, metadataUri)
       .accounts({
         component: componentPda,
+        creatorProfile: creatorProfilePda,
         marketplace: marketplacePda,
         creator: this.provider.wallet.publicKey,
       })
       .rpc();
   }
 
+  async migrateComponent(componentId: string) {
+    const [legacyComponentPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('component'), Buffer.from(componentId)],
+      this.program.programId
+    );
+
+    const [componentPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('component'),
+        this.provider.wallet.publicKey.toBuffer(),
+        Buffer.from(componentId)
+      ],
+      this.program.programId
+    );
+
+    const [creatorProfilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('creator_profile'), this.provider.wallet.publicKey.toBuffer()],
+      this.program.programId
+    );
+
+    return await this.program.methods
+      .migrateComponent(componentId)
+      .accounts({
+        legacyComponent: legacyComponentPda,
+        component: componentPda,
+        creatorProfile: creatorProfilePda,
+        creator: this.provider.wallet.publicKey,
+      })
+      .rpc();
+  }
+
+  async updateCreatorProfile(
+    handle: string,
+    profileUri: string,
+    payoutWallet: PublicKey
+  ) {
+    const [creatorProfilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('creator_profile'), this.provider.wallet.publicKey.toBuffer()],
+      this.program.programId
+    );
+
+    return await this.program.methods
+      .updateCreatorProfile(handle, profileUri, payoutWallet)
+      .accounts({
+        creatorProfile: creatorProfilePda,
+        creator: this.provider.wallet.publicKey,
+      })
+      .rpc();
+  }
+
   async purchaseComponent(
-    componentId: string,
     component: PublicKey,
+    fliiTokenMint: PublicKey,
+    buyerTokenAccount: PublicKey,
     creatorTokenAccount: PublicKey,
-    marketplaceTokenAccount: PublicKey
+    maxPrice: BN, // Cap on the amount debited: price plus the 2% staking reward
+    recipient: PublicKey | null = null,
+    giftMessageHash: number[] | null = null
   ) {
-    const [purchasePda] = PublicKey.findProgramAddressSync(
+    const componentAccount = await this.program.account.component.fetch(component);
+
+    const [creatorProfilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('creator_profile'), componentAccount.creator.toBuffer()],
+      this.program.programId
+    );
+
+    const [licensePda] = PublicKey.findProgramAddressSync(
       [
-        Buffer.from('purchase'),
-        this.provider.wallet.publicKey.toBuffer(),
-        Buffer.from(componentId)
+        Buffer.from('license'),
+        component.toBuffer(),
+        componentAccount.totalSales.toArrayLike(Buffer, 'le', 8)
+      ],
+      this.program.programId
+    );
+
+    const [walletPurchasesPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('wallet_purchases'),
+        component.toBuffer(),
+        (recipient ?? this.provider.wallet.publicKey).toBuffer()
       ],
       this.program.programId
     );
@@ -82,15 +161,24 @@ export class MarketplaceClient {
       this.program.programId
     );
 
+    const [feeVaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('fee_vault')],
+      this.program.programId
+    );
+
     return await this.program.methods
-      .purchaseComponent()
+      .purchaseComponent(recipient, giftMessageHash, maxPrice)
       .accounts({
         component,
+        creatorProfile: creatorProfilePda,
         marketplace: marketplacePda,
-        purchase: purchasePda,
+        fliiTokenMint,
         buyer: this.provider.wallet.publicKey,
-        creatorTokenAccount,
-        marketplaceTokenAccount,
+        buyerFliiTokenAccount: buyerTokenAccount,
+        creatorFliiTokenAccount: creatorTokenAccount,
+        feeVault: feeVaultPda,
+        license: licensePda,
+        walletPurchases: walletPurchasesPda,
       })
       .rpc();
   }
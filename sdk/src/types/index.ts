@@ -5,6 +5,32 @@ export interface Marketplace {
   feePercentage: number;
   totalVolume: bigint;
   totalComponents: bigint;
+  feeTiers: FeeTier[];
+  burnBps: number;
+  totalBurned: bigint;
+  treasurySplit: TreasuryLeg[];
+  totalFeesCollected: bigint;
+  totalFeesWithdrawn: bigint;
+  totalNftSales: bigint;
+  totalResaleVolume: bigint;
+  totalLicenseResales: bigint;
+}
+
+export type TreasuryLegKind =
+  | { operations: {} }
+  | { stakingRewards: {} }
+  | { grants: {} }
+  | { burn: {} };
+
+export interface TreasuryLeg {
+  kind: TreasuryLegKind;
+  destination: PublicKey;
+  bps: number;
+}
+
+export interface FeeTier {
+  minBalance: bigint;
+  feePercentage: number;
 }
 
 export interface Component {
@@ -15,8 +41,56 @@ export interface Component {
   isActive: boolean;
   totalSales: bigint;
   createdAt: bigint;
+  isFrozen: boolean;
+  freezeReason: number;
+  pendingCreator: PublicKey | null;
+  maxSupply: bigint | null;
+  maxPerWallet: number | null;
+  saleStart: bigint;
+  saleWindows: SaleWindow[];
+  dutchAuction: DutchAuction | null;
+  licenseMode: LicenseMode;
+  resaleRoyaltyBps: number;
 }
 
+export type LicenseMode = { receipt: {} } | { transferable: {} } | { soulbound: {} };
+
+export interface DutchAuction {
+  startPrice: bigint;
+  floorPrice: bigint;
+  startTime: bigint;
+  decayInterval: bigint;
+  decayAmount: bigint;
+}
+
+export interface SaleWindow {
+  start: bigint;
+  end: bigint;
+  price: bigint;
+}
+
+export interface CreatorProfile {
+  creator: PublicKey;
+  handle: string;
+  profileUri: string;
+  payoutWallet: PublicKey;
+  totalListings: bigint;
+  totalSales: bigint;
+  lifetimeEarnings: bigint;
+  lifetimeRewards: bigint;
+  createdAt: bigint;
+  verified: boolean;
+  verifiedAt: bigint;
+  isBanned: boolean;
+  banReason: number;
+  earningsVaultEnabled: boolean;
+  earningsVault: PublicKey;
+  totalClaimed: bigint;
+}
+
+/**
+ * @deprecated Purchases are recorded as `License` accounts.
+ */
 export interface Purchase {
   buyer: PublicKey;
   componentId: string;
@@ -24,14 +98,114 @@ export interface Purchase {
   purchasedAt: bigint;
 }
 
+export interface License {
+  component: PublicKey;
+  owner: PublicKey;
+  purchaser: PublicKey;
+  price: bigint;
+  purchasedAt: bigint;
+  giftMessageHash: number[] | null;
+  edition: bigint;
+  licenseMint: PublicKey | null;
+}
+
+export interface Auction {
+  component: PublicKey;
+  creator: PublicKey;
+  reservePrice: bigint;
+  minIncrement: bigint;
+  startTime: bigint;
+  endTime: bigint;
+  extensionWindow: bigint;
+  highestBid: bigint;
+  highestBidder: PublicKey | null;
+  bump: number;
+}
+
+export interface Offer {
+  buyer: PublicKey;
+  component: PublicKey;
+  amount: bigint;
+  createdAt: bigint;
+  expiresAt: bigint | null;
+  bump: number;
+}
+
+export interface LicenseListing {
+  seller: PublicKey;
+  license: PublicKey;
+  price: bigint;
+  createdAt: bigint;
+  bump: number;
+}
+
+export interface NFTListing {
+  seller: PublicKey;
+  nftMint: PublicKey;
+  price: bigint;
+  createdAt: bigint;
+  bump: number;
+}
+
 export interface ComponentListedEvent {
   componentId: string;
   creator: PublicKey;
   price: bigint;
+  creatorVerified: boolean;
 }
 
 export interface ComponentPurchasedEvent {
   componentId: string;
   buyer: PublicKey;
   price: bigint;
+  effectivePrice: bigint;
+  recipient: PublicKey;
+  license: PublicKey;
+  giftMessageHash: number[] | null;
+  edition: bigint;
+  platformFee: bigint;
+  feeTier: number | null;
+  burned: bigint;
+}
+
+export interface StakePool {
+  fliiMint: PublicKey;
+  stakeVault: PublicKey;
+  rewardVault: PublicKey;
+  totalStaked: bigint;
+  totalWeight: bigint;
+  rewardPerToken: bigint;
+  queuedRewards: bigint;
+  totalRewardsDistributed: bigint;
+  totalRewardsClaimed: bigint;
+  bump: number;
+}
+
+export interface StakePosition {
+  owner: PublicKey;
+  pool: PublicKey;
+  amount: bigint;
+  rewardPerTokenPaid: bigint;
+  pendingRewards: bigint;
+  bump: number;
+  lockStart: bigint;
+  lockEnd: bigint;
+  boostBps: number;
+  weight: bigint;
+}
+
+export interface VestingSchedule {
+  grantor: PublicKey;
+  beneficiary: PublicKey;
+  mint: PublicKey;
+  vault: PublicKey;
+  scheduleId: bigint;
+  totalAmount: bigint;
+  claimedAmount: bigint;
+  startTime: bigint;
+  cliffTime: bigint;
+  endTime: bigint;
+  revocable: boolean;
+  revoked: boolean;
+  bump: number;
 }
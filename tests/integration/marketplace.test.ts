@@ -1,7 +1,29 @@
 import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
 import { Marketplace } from '../../target/types/marketplace';
+import {
+  createAssociatedTokenAccount,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  mintTo,
+} from '@solana/spl-token';
+import {
+  PROGRAM_ID as TOKEN_METADATA_PROGRAM_ID,
+  createCreateMetadataAccountV3Instruction,
+  createSignMetadataInstruction,
+} from '@metaplex-foundation/mpl-token-metadata';
 import { assert, expect } from 'chai';
+import * as fs from 'fs';
+import * as path from 'path';
+
+// Loaded into the local validator at the FLII mint address by Anchor.toml
+const FLII_TOKEN_MINT = new anchor.web3.PublicKey('BMge7se4AqyTqEpcTSHURzA4YG9rNvmHscFEFJK9pump');
+const fliiMintAuthority = anchor.web3.Keypair.fromSecretKey(
+  Uint8Array.from(
+    JSON.parse(fs.readFileSync(path.join(__dirname, '../fixtures/flii-mint-authority.json'), 'utf8'))
+  )
+);
 
 describe('marketplace', () => {
   // Skip tests in CI environment when no local validator is running
@@ -26,15 +48,17 @@ describe('marketplace', () => {
     );
 
     await program.methods
-      .initialize(3000) // 30% fee
+      .initialize(250) // 2.5% fee
       .accounts({
         marketplace: marketplacePda,
+        fliiTokenMint: FLII_TOKEN_MINT,
+        treasuryWallet: provider.wallet.publicKey,
         authority: provider.wallet.publicKey,
       })
       .rpc();
 
     const marketplace = await program.account.marketplace.fetch(marketplacePda);
-    assert.equal(marketplace.feePercentage, 3000);
+    assert.equal(marketplace.feePercentage, 250);
     assert.equal(marketplace.authority.toString(), provider.wallet.publicKey.toString());
   });
 
@@ -44,7 +68,16 @@ describe('marketplace', () => {
     const metadataUri = 'https://example.com/metadata.json';
 
     const [componentPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from('component'), Buffer.from(componentId)],
+      [
+        Buffer.from('component'),
+        provider.wallet.publicKey.toBuffer(),
+        Buffer.from(componentId)
+      ],
+      program.programId
+    );
+
+    const [creatorProfilePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('creator_profile'), provider.wallet.publicKey.toBuffer()],
       program.programId
     );
 
@@ -57,6 +90,7 @@ describe('marketplace', () => {
       .listComponent(componentId, price, metadataUri)
       .accounts({
         component: componentPda,
+        creatorProfile: creatorProfilePda,
         marketplace: marketplacePda,
         creator: provider.wallet.publicKey,
       })
@@ -67,5 +101,169 @@ describe('marketplace', () => {
     assert.equal(component.price.toString(), price.toString());
     assert.equal(component.metadataUri, metadataUri);
     assert.equal(component.isActive, true);
+
+    const creatorProfile = await program.account.creatorProfile.fetch(creatorProfilePda);
+    assert.equal(creatorProfile.creator.toString(), provider.wallet.publicKey.toString());
+    assert.equal(creatorProfile.payoutWallet.toString(), provider.wallet.publicKey.toString());
+    assert.equal(creatorProfile.totalListings.toNumber(), 1);
+  });
+
+  it('Pays royalties to verified creators when an NFT sells', async () => {
+    const connection = provider.connection;
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const seller = provider.wallet.publicKey;
+    const buyer = anchor.web3.Keypair.generate();
+    const creatorA = anchor.web3.Keypair.generate();
+    const creatorB = anchor.web3.Keypair.generate();
+    const unverifiedCreator = anchor.web3.Keypair.generate();
+    const price = new anchor.BN(1_000_000_000); // 1,000 FLII
+    const sellerFeeBasisPoints = 1000; // 10% royalty
+
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(buyer.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+      'confirmed'
+    );
+
+    const [marketplacePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('marketplace')],
+      program.programId
+    );
+    const [feeVaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('fee_vault')],
+      program.programId
+    );
+
+    await program.methods
+      .initializeFeeVault()
+      .accounts({
+        marketplace: marketplacePda,
+        fliiTokenMint: FLII_TOKEN_MINT,
+        feeVault: feeVaultPda,
+        authority: seller,
+      })
+      .rpc();
+
+    // Mint a 1/1 NFT to the seller with two verified creators and one unverified
+    const nftMint = await createMint(connection, payer, seller, null, 0);
+    const sellerNftAccount = await createAssociatedTokenAccount(connection, payer, nftMint, seller);
+    await mintTo(connection, payer, nftMint, sellerNftAccount, payer, 1);
+
+    const [nftMetadata] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), nftMint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    );
+    const metadataTx = new anchor.web3.Transaction().add(
+      createCreateMetadataAccountV3Instruction(
+        {
+          metadata: nftMetadata,
+          mint: nftMint,
+          mintAuthority: seller,
+          payer: seller,
+          updateAuthority: seller,
+        },
+        {
+          createMetadataAccountArgsV3: {
+            data: {
+              name: 'Royalty Test',
+              symbol: 'FLII',
+              uri: 'https://example.com/nft.json',
+              sellerFeeBasisPoints,
+              creators: [
+                { address: creatorA.publicKey, verified: false, share: 60 },
+                { address: creatorB.publicKey, verified: false, share: 30 },
+                { address: unverifiedCreator.publicKey, verified: false, share: 10 },
+              ],
+              collection: null,
+              uses: null,
+            },
+            isMutable: true,
+            collectionDetails: null,
+          },
+        }
+      ),
+      createSignMetadataInstruction({ metadata: nftMetadata, creator: creatorA.publicKey }),
+      createSignMetadataInstruction({ metadata: nftMetadata, creator: creatorB.publicKey })
+    );
+    await provider.sendAndConfirm(metadataTx, [creatorA, creatorB]);
+
+    // FLII accounts for every party; only the buyer holds a balance
+    const [buyerFlii, sellerFlii, creatorAFlii, creatorBFlii, unverifiedFlii] = await Promise.all(
+      [buyer, payer, creatorA, creatorB, unverifiedCreator].map((owner) =>
+        createAssociatedTokenAccount(connection, payer, FLII_TOKEN_MINT, owner.publicKey)
+      )
+    );
+    await mintTo(
+      connection,
+      payer,
+      FLII_TOKEN_MINT,
+      buyerFlii,
+      fliiMintAuthority,
+      BigInt(price.toString())
+    );
+
+    const [listingPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('nft_listing'), nftMint.toBuffer()],
+      program.programId
+    );
+    const [escrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from('nft_escrow'), nftMint.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createNftListing(price)
+      .accounts({
+        listing: listingPda,
+        escrowNftAccount: escrowPda,
+        nftMint,
+        sellerNftAccount,
+        marketplace: marketplacePda,
+        seller,
+      })
+      .rpc();
+
+    // One FLII account per verified creator, in metadata order
+    await program.methods
+      .purchaseNft()
+      .accounts({
+        listing: listingPda,
+        escrowNftAccount: escrowPda,
+        nftMint,
+        nftMetadata,
+        marketplace: marketplacePda,
+        seller,
+        buyer: buyer.publicKey,
+        buyerFliiTokenAccount: buyerFlii,
+        buyerNftAccount: getAssociatedTokenAddressSync(nftMint, buyer.publicKey),
+        sellerFliiTokenAccount: sellerFlii,
+        feeVault: feeVaultPda,
+      })
+      .remainingAccounts([
+        { pubkey: creatorAFlii, isWritable: true, isSigner: false },
+        { pubkey: creatorBFlii, isWritable: true, isSigner: false },
+      ])
+      .signers([buyer])
+      .rpc();
+
+    const balance = async (account: anchor.web3.PublicKey) =>
+      (await getAccount(connection, account)).amount;
+
+    const total = BigInt(price.toString());
+    const platformFee = (total * BigInt(250)) / BigInt(10000);
+    const royalty = (total * BigInt(sellerFeeBasisPoints)) / BigInt(10000);
+    const royaltyA = (royalty * BigInt(60)) / BigInt(100);
+    const royaltyB = (royalty * BigInt(30)) / BigInt(100);
+
+    assert.equal(await balance(creatorAFlii), royaltyA);
+    assert.equal(await balance(creatorBFlii), royaltyB);
+    // The unverified creator's share stays with the seller
+    assert.equal(await balance(unverifiedFlii), BigInt(0));
+    assert.equal(await balance(sellerFlii), total - platformFee - royaltyA - royaltyB);
+    assert.equal(await balance(feeVaultPda), platformFee);
+    assert.equal(await balance(buyerFlii), BigInt(0));
+    assert.equal(
+      await balance(getAssociatedTokenAddressSync(nftMint, buyer.publicKey)),
+      BigInt(1)
+    );
   });
 });